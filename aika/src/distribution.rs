@@ -1,49 +1,824 @@
 //! Distribution module. Contains the `Distribution` trait which allows for the creation of custom distributions to be used in the `ProcessExecution::Stochastic` variant.
 //! Distributions must enforce a sampling of only positive real numbers, as this describes a time delta moving forward.
 
-use rand::Rng;
-use rand_distr::{Gamma as GammaDistribution, Poisson as PoissonDistribution};
+use crate::threading::MaybeSendSync;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+use rand::{Rng, RngCore};
+use rand_distr::{
+    Beta as BetaDistribution, Exp as ExpDistribution, Gamma as GammaDistribution,
+    Normal as NormalDistribution, Poisson as PoissonDistribution,
+};
+
+/// Errors returned when a distribution is constructed with parameters that could produce invalid time deltas.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DistributionError {
+    /// A bound was negative. Time deltas must be non-negative.
+    NegativeBound,
+    /// The upper bound was not strictly greater than the lower bound.
+    EmptyRange,
+    /// No samples were supplied.
+    EmptySamples,
+    /// A supplied sample was negative.
+    NegativeSample,
+    /// A fixed value was negative.
+    NegativeValue,
+    /// A parameter that must be strictly positive was zero or negative.
+    NonPositiveParameter,
+    /// A mixture was given no components.
+    EmptyComponents,
+    /// A mixture weight was negative or not finite, or the weights summed to zero.
+    InvalidWeights,
+    /// A probability was outside `(0, 1]`.
+    InvalidProbability,
+    /// The distribution has no closed-form inverse CDF.
+    NoInverseCdf,
+    /// The distribution has no closed-form mean.
+    NoMean,
+    /// Parameters describing the same states had different lengths.
+    DimensionMismatch,
+    /// A mode was outside the range it must lie in.
+    ModeOutOfRange,
+    /// A sampled time delta was zero or not a number, so time could not advance.
+    NoProgress,
+    /// A parameter was infinite or not a number.
+    NonFiniteParameter,
+}
+
+impl fmt::Display for DistributionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistributionError::NegativeBound => {
+                write!(f, "distribution bound must be non-negative")
+            }
+            DistributionError::EmptyRange => {
+                write!(f, "upper bound must be greater than lower bound")
+            }
+            DistributionError::EmptySamples => write!(f, "sample data must not be empty"),
+            DistributionError::NegativeSample => write!(f, "sample data must be non-negative"),
+            DistributionError::NegativeValue => write!(f, "value must be non-negative"),
+            DistributionError::NonPositiveParameter => write!(f, "parameter must be positive"),
+            DistributionError::EmptyComponents => write!(f, "mixture must have components"),
+            DistributionError::InvalidProbability => {
+                write!(f, "probability must be in (0, 1]")
+            }
+            DistributionError::NoInverseCdf => {
+                write!(f, "distribution must have a closed-form inverse CDF")
+            }
+            DistributionError::NoMean => write!(f, "distribution must have a closed-form mean"),
+            DistributionError::DimensionMismatch => {
+                write!(f, "parameters must have one entry per state")
+            }
+            DistributionError::ModeOutOfRange => write!(f, "mode must lie within the bounds"),
+            DistributionError::NoProgress => write!(f, "sampled time delta must be positive"),
+            DistributionError::NonFiniteParameter => write!(f, "parameter must be finite"),
+            DistributionError::InvalidWeights => {
+                write!(
+                    f,
+                    "mixture weights must be finite and non-negative with a positive sum"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DistributionError {}
 
 /// The `Distribution` trait allows for the creation of custom distributions to be used in the `ProcessExecution::Stochastic` variant.
-pub trait Distribution {
+/// Implementors must be `Clone` so that boxed distributions, and the `ProcessExecution` holding them, can be duplicated,
+/// and, with the `rayon` feature, `Send + Sync` so that environments can be run on other threads. Sampling takes any
+/// random number generator, so a distribution works with whichever generator the `Environment` was created with.
+pub trait Distribution: DistributionClone + MaybeSendSync {
     /// Sample the distribution for time delta value.
-    fn sample(&self, rng: &mut rand::rngs::StdRng) -> f64;
+    fn sample(&self, rng: &mut dyn RngCore) -> f64;
+
+    /// Sample the distribution `n` times, collecting the time delta values in draw order.
+    fn sample_n(&self, rng: &mut dyn RngCore, n: usize) -> Vec<f64> {
+        (0..n).map(|_| self.sample(rng)).collect()
+    }
+
+    /// The inverse cumulative distribution function at `u` in `[0, 1]`, if it has a closed form.
+    fn inv_cdf(&self, _u: f64) -> Option<f64> {
+        None
+    }
+
+    /// The theoretical mean, if it has a closed form.
+    fn mean(&self) -> Option<f64> {
+        None
+    }
+
+    /// The theoretical variance, if it has a closed form.
+    fn variance(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Object-safe cloning for boxed distributions. Implemented automatically for every `Distribution + Clone`.
+pub trait DistributionClone {
+    /// Clone the distribution into a new box.
+    fn clone_box(&self) -> Box<dyn Distribution>;
+}
+
+impl<D: Distribution + Clone + 'static> DistributionClone for D {
+    fn clone_box(&self) -> Box<dyn Distribution> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Distribution> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
 }
 
 /// The `Poisson` struct implements the `Distribution` trait for the Poisson distribution.
+#[derive(Clone)]
 pub struct Poisson {
+    pub lambda: f64,
     pub distribution: PoissonDistribution<f64>,
 }
 
 impl Poisson {
-    pub fn new(lambda: f64) -> Poisson {
-        Self {
-            distribution: PoissonDistribution::new(lambda).unwrap(),
+    pub fn new(lambda: f64) -> Result<Poisson, DistributionError> {
+        if lambda.is_nan() || lambda <= 0.0 {
+            return Err(DistributionError::NonPositiveParameter);
         }
+        Ok(Self {
+            lambda,
+            distribution: PoissonDistribution::new(lambda).unwrap(),
+        })
     }
 }
 
 impl Distribution for Poisson {
-    fn sample(&self, rng: &mut rand::rngs::StdRng) -> f64 {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
         rng.sample(self.distribution)
     }
+
+    fn mean(&self) -> Option<f64> {
+        Some(self.lambda)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some(self.lambda)
+    }
 }
 
 /// The `Gamma` struct implements the `Distribution` trait for the Gamma distribution.
+#[derive(Clone)]
 pub struct Gamma {
+    pub shape: f64,
+    pub scale: f64,
     pub distribution: GammaDistribution<f64>,
 }
 
 impl Gamma {
-    pub fn new(shape: f64, scale: f64) -> Gamma {
-        Self {
-            distribution: GammaDistribution::new(shape, scale).unwrap(),
+    pub fn new(shape: f64, scale: f64) -> Result<Gamma, DistributionError> {
+        if shape.is_nan() || shape <= 0.0 || scale.is_nan() || scale <= 0.0 {
+            return Err(DistributionError::NonPositiveParameter);
         }
+        Ok(Self {
+            shape,
+            scale,
+            distribution: GammaDistribution::new(shape, scale).unwrap(),
+        })
     }
 }
 
 impl Distribution for Gamma {
-    fn sample(&self, rng: &mut rand::rngs::StdRng) -> f64 {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rng.sample(self.distribution)
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some(self.shape * self.scale)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some(self.shape * self.scale * self.scale)
+    }
+}
+
+/// The `Exponential` struct implements the `Distribution` trait for the exponential distribution with rate `lambda`.
+#[derive(Clone)]
+pub struct Exponential {
+    pub lambda: f64,
+    pub distribution: ExpDistribution<f64>,
+}
+
+impl Exponential {
+    pub fn new(lambda: f64) -> Result<Exponential, DistributionError> {
+        if !lambda.is_finite() {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if lambda <= 0.0 {
+            return Err(DistributionError::NonPositiveParameter);
+        }
+        let distribution =
+            ExpDistribution::new(lambda).map_err(|_| DistributionError::NonPositiveParameter)?;
+        Ok(Self {
+            lambda,
+            distribution,
+        })
+    }
+}
+
+impl Distribution for Exponential {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
         rng.sample(self.distribution)
     }
+
+    fn inv_cdf(&self, u: f64) -> Option<f64> {
+        Some(-(1.0 - u).ln() / self.lambda)
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some(1.0 / self.lambda)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some(1.0 / (self.lambda * self.lambda))
+    }
+}
+
+/// The `Uniform` struct implements the `Distribution` trait for a continuous uniform distribution over `[low, high)`.
+#[derive(Clone)]
+pub struct Uniform {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Uniform {
+    pub fn new(low: f64, high: f64) -> Result<Uniform, DistributionError> {
+        if !low.is_finite() || !high.is_finite() {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if low < 0.0 {
+            return Err(DistributionError::NegativeBound);
+        }
+        if high <= low {
+            return Err(DistributionError::EmptyRange);
+        }
+        Ok(Self { low, high })
+    }
+}
+
+impl Distribution for Uniform {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rng.gen_range(self.low..self.high)
+    }
+
+    fn inv_cdf(&self, u: f64) -> Option<f64> {
+        Some(self.low + u * (self.high - self.low))
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some((self.low + self.high) / 2.0)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some((self.high - self.low).powi(2) / 12.0)
+    }
+}
+
+/// The `Empirical` struct implements the `Distribution` trait by resampling user-supplied data, e.g. measured service times.
+#[derive(Clone)]
+pub struct Empirical {
+    pub data: Vec<f64>,
+}
+
+impl Empirical {
+    pub fn new(data: Vec<f64>) -> Result<Empirical, DistributionError> {
+        if data.is_empty() {
+            return Err(DistributionError::EmptySamples);
+        }
+        if data.iter().any(|value| !value.is_finite()) {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if data.iter().any(|value| *value < 0.0) {
+            return Err(DistributionError::NegativeSample);
+        }
+        Ok(Self { data })
+    }
+}
+
+impl Distribution for Empirical {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        self.data[rng.gen_range(0..self.data.len())]
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some(self.data.iter().sum::<f64>() / self.data.len() as f64)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        Some(
+            self.data
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / self.data.len() as f64,
+        )
+    }
+}
+
+/// The `DeterministicDist` struct implements the `Distribution` trait by always returning a fixed value, ignoring the rng.
+/// Useful for swapping out randomness in a `ProcessExecution::Stochastic` process while debugging.
+#[derive(Clone)]
+pub struct DeterministicDist {
+    pub value: f64,
+}
+
+impl DeterministicDist {
+    pub fn new(value: f64) -> Result<DeterministicDist, DistributionError> {
+        if !value.is_finite() {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if value < 0.0 {
+            return Err(DistributionError::NegativeValue);
+        }
+        Ok(Self { value })
+    }
+}
+
+impl Distribution for DeterministicDist {
+    fn sample(&self, _rng: &mut dyn RngCore) -> f64 {
+        self.value
+    }
+
+    fn inv_cdf(&self, _u: f64) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some(self.value)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+/// The `Truncated` struct bounds the samples of any inner `Distribution` to `[min, max]`.
+/// Samples outside the bounds are re-drawn up to `max_retries` times, after which the last draw is clamped.
+/// The number of draws consumed is deterministic for a given seed, so runs stay reproducible.
+#[derive(Clone)]
+pub struct Truncated<D: Distribution> {
+    pub inner: D,
+    pub min: f64,
+    pub max: f64,
+    pub max_retries: usize,
+}
+
+impl<D: Distribution> Truncated<D> {
+    /// Default number of re-draws before clamping.
+    pub const DEFAULT_MAX_RETRIES: usize = 100;
+
+    pub fn new(inner: D, min: f64, max: f64) -> Result<Truncated<D>, DistributionError> {
+        if !min.is_finite() || !max.is_finite() {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if min < 0.0 {
+            return Err(DistributionError::NegativeBound);
+        }
+        if max < min {
+            return Err(DistributionError::EmptyRange);
+        }
+        Ok(Self {
+            inner,
+            min,
+            max,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+        })
+    }
+
+    /// Set the number of re-draws attempted before clamping.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+}
+
+impl<D: Distribution + Clone + 'static> Distribution for Truncated<D> {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        let mut value = self.inner.sample(rng);
+        for _ in 0..self.max_retries {
+            if value >= self.min && value <= self.max {
+                return value;
+            }
+            value = self.inner.sample(rng);
+        }
+        value.clamp(self.min, self.max)
+    }
+}
+
+/// The `HalfNormal` struct implements the `Distribution` trait for the half-normal distribution.
+/// Draws from a zero-mean normal are reflected to their absolute value so time deltas are never negative.
+#[derive(Clone)]
+pub struct HalfNormal {
+    pub distribution: NormalDistribution<f64>,
+}
+
+impl HalfNormal {
+    pub fn new(std_dev: f64) -> Result<HalfNormal, DistributionError> {
+        if !std_dev.is_finite() {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if std_dev <= 0.0 {
+            return Err(DistributionError::NonPositiveParameter);
+        }
+        let distribution = NormalDistribution::new(0.0, std_dev)
+            .map_err(|_| DistributionError::NonPositiveParameter)?;
+        Ok(Self { distribution })
+    }
+}
+
+impl Distribution for HalfNormal {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rng.sample(self.distribution).abs()
+    }
+}
+
+/// The `Triangular` struct implements the `Distribution` trait for the triangular distribution over `[min, max]` peaking
+/// at `mode`. A common model for task durations given optimistic, most likely and pessimistic estimates.
+#[derive(Clone)]
+pub struct Triangular {
+    pub min: f64,
+    pub mode: f64,
+    pub max: f64,
+}
+
+impl Triangular {
+    pub fn new(min: f64, mode: f64, max: f64) -> Result<Triangular, DistributionError> {
+        if !min.is_finite() || !mode.is_finite() || !max.is_finite() {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if min < 0.0 {
+            return Err(DistributionError::NegativeBound);
+        }
+        if max <= min {
+            return Err(DistributionError::EmptyRange);
+        }
+        if mode < min || mode > max {
+            return Err(DistributionError::ModeOutOfRange);
+        }
+        Ok(Self { min, mode, max })
+    }
+}
+
+impl Distribution for Triangular {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        self.inv_cdf(rng.gen()).unwrap()
+    }
+
+    fn inv_cdf(&self, u: f64) -> Option<f64> {
+        let range = self.max - self.min;
+        let split = (self.mode - self.min) / range;
+        Some(if u < split {
+            self.min + (u * range * (self.mode - self.min)).sqrt()
+        } else {
+            self.max - ((1.0 - u) * range * (self.max - self.mode)).sqrt()
+        })
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some((self.min + self.mode + self.max) / 3.0)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        let (a, c, b) = (self.min, self.mode, self.max);
+        Some((a * a + b * b + c * c - a * b - a * c - b * c) / 18.0)
+    }
+}
+
+/// The `Beta` struct implements the `Distribution` trait for the beta distribution with shape parameters `alpha` and
+/// `beta`, rescaled from `[0, 1]` to `[min, max]`. Used for PERT-style task durations.
+#[derive(Clone)]
+pub struct Beta {
+    pub min: f64,
+    pub max: f64,
+    pub distribution: BetaDistribution<f64>,
+}
+
+impl Beta {
+    pub fn new(alpha: f64, beta: f64, min: f64, max: f64) -> Result<Beta, DistributionError> {
+        if [alpha, beta, min, max]
+            .iter()
+            .any(|param| !param.is_finite())
+        {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if alpha <= 0.0 || beta <= 0.0 {
+            return Err(DistributionError::NonPositiveParameter);
+        }
+        if min < 0.0 {
+            return Err(DistributionError::NegativeBound);
+        }
+        if max <= min {
+            return Err(DistributionError::EmptyRange);
+        }
+        let distribution = BetaDistribution::new(alpha, beta)
+            .map_err(|_| DistributionError::NonPositiveParameter)?;
+        Ok(Self {
+            min,
+            max,
+            distribution,
+        })
+    }
+}
+
+impl Distribution for Beta {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        self.min + rng.sample(self.distribution) * (self.max - self.min)
+    }
+}
+
+/// Validate that `p` is a probability in `(0, 1]`.
+fn check_probability(p: f64) -> Result<(), DistributionError> {
+    if p.is_nan() || p <= 0.0 || p > 1.0 {
+        return Err(DistributionError::InvalidProbability);
+    }
+    Ok(())
+}
+
+/// The `Bernoulli` struct implements the `Distribution` trait by returning `1.0` with probability `p` and `0.0` otherwise.
+#[derive(Clone)]
+pub struct Bernoulli {
+    pub p: f64,
+}
+
+impl Bernoulli {
+    pub fn new(p: f64) -> Result<Bernoulli, DistributionError> {
+        check_probability(p)?;
+        Ok(Self { p })
+    }
+}
+
+impl Distribution for Bernoulli {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        self.inv_cdf(rng.gen()).unwrap()
+    }
+
+    fn inv_cdf(&self, u: f64) -> Option<f64> {
+        Some(if u < 1.0 - self.p { 0.0 } else { 1.0 })
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some(self.p)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some(self.p * (1.0 - self.p))
+    }
+}
+
+/// The `Geometric` struct implements the `Distribution` trait for the number of trials, each succeeding with probability
+/// `p`, up to and including the first success. Samples are whole numbers of at least one with mean `1 / p`.
+#[derive(Clone)]
+pub struct Geometric {
+    pub p: f64,
+}
+
+impl Geometric {
+    pub fn new(p: f64) -> Result<Geometric, DistributionError> {
+        check_probability(p)?;
+        Ok(Self { p })
+    }
+}
+
+impl Distribution for Geometric {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        self.inv_cdf(rng.gen()).unwrap()
+    }
+
+    fn inv_cdf(&self, u: f64) -> Option<f64> {
+        if self.p == 1.0 {
+            return Some(1.0);
+        }
+        Some(((1.0 - u).ln() / (1.0 - self.p).ln()).ceil().max(1.0))
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some(1.0 / self.p)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some((1.0 - self.p) / (self.p * self.p))
+    }
+}
+
+/// The `DiscreteUniform` struct implements the `Distribution` trait by returning a whole number drawn uniformly from
+/// `[low, high]`, both inclusive.
+#[derive(Clone)]
+pub struct DiscreteUniform {
+    pub low: u64,
+    pub high: u64,
+}
+
+impl DiscreteUniform {
+    pub fn new(low: u64, high: u64) -> Result<DiscreteUniform, DistributionError> {
+        if high < low {
+            return Err(DistributionError::EmptyRange);
+        }
+        Ok(Self { low, high })
+    }
+}
+
+impl Distribution for DiscreteUniform {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        rng.gen_range(self.low..=self.high) as f64
+    }
+
+    fn mean(&self) -> Option<f64> {
+        Some((self.low as f64 + self.high as f64) / 2.0)
+    }
+
+    fn variance(&self) -> Option<f64> {
+        Some((((self.high - self.low) as f64 + 1.0).powi(2) - 1.0) / 12.0)
+    }
+}
+
+/// The `Mixture` struct implements the `Distribution` trait by picking a component proportionally to its weight and
+/// sampling from it. Weights are normalized to sum to one on construction.
+#[derive(Clone)]
+pub struct Mixture {
+    pub components: Vec<(f64, Box<dyn Distribution>)>,
+}
+
+impl Mixture {
+    pub fn new(
+        components: Vec<(f64, Box<dyn Distribution>)>,
+    ) -> Result<Mixture, DistributionError> {
+        if components.is_empty() {
+            return Err(DistributionError::EmptyComponents);
+        }
+        if components
+            .iter()
+            .any(|(weight, _)| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err(DistributionError::InvalidWeights);
+        }
+        let total: f64 = components.iter().map(|(weight, _)| weight).sum();
+        if total <= 0.0 {
+            return Err(DistributionError::InvalidWeights);
+        }
+        Ok(Self {
+            components: components
+                .into_iter()
+                .map(|(weight, distribution)| (weight / total, distribution))
+                .collect(),
+        })
+    }
+}
+
+impl Distribution for Mixture {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        let mut pick = rng.gen_range(0.0..1.0);
+        for (weight, distribution) in &self.components {
+            if pick < *weight {
+                return distribution.sample(rng);
+            }
+            pick -= weight;
+        }
+        // Rounding can leave `pick` just past the final cumulative weight.
+        let (_, last) = self
+            .components
+            .iter()
+            .rev()
+            .find(|(weight, _)| *weight > 0.0)
+            .unwrap();
+        last.sample(rng)
+    }
+}
+
+/// The `Antithetic` struct samples an inner `Distribution` by inverse transform of a uniform draw `u`, using `1 - u`
+/// instead when `reflected` is set. Two environments built with the same seed, one reflected and one not, form an
+/// antithetic pair whose time deltas are negatively correlated. The inner distribution must have a closed-form
+/// inverse CDF.
+#[derive(Clone)]
+pub struct Antithetic<D: Distribution> {
+    pub inner: D,
+    pub reflected: bool,
+}
+
+impl<D: Distribution> Antithetic<D> {
+    pub fn new(inner: D, reflected: bool) -> Result<Antithetic<D>, DistributionError> {
+        if inner.inv_cdf(0.5).is_none() {
+            return Err(DistributionError::NoInverseCdf);
+        }
+        Ok(Self { inner, reflected })
+    }
+}
+
+impl<D: Distribution + Clone + 'static> Distribution for Antithetic<D> {
+    fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        let u: f64 = rng.gen();
+        self.inv_cdf(u).unwrap()
+    }
+
+    fn inv_cdf(&self, u: f64) -> Option<f64> {
+        self.inner.inv_cdf(if self.reflected { 1.0 - u } else { u })
+    }
+}
+
+/// The `MarkovModulated` struct describes a Markov-modulated Poisson arrival process for bursty traffic. A modulating
+/// chain stays in state `i` for a time drawn from `holding[i]`, during which arrivals occur as a Poisson process with
+/// rate `rates[i]`, then moves to state `j` with probability proportional to `transitions[i][j]`. The chain starts in
+/// state `0`. Holding times must have a positive mean, so that the chain keeps moving forward in time.
+#[derive(Clone)]
+pub struct MarkovModulated {
+    pub rates: Vec<f64>,
+    pub transitions: Vec<Vec<f64>>,
+    pub holding: Vec<Box<dyn Distribution>>,
+}
+
+impl MarkovModulated {
+    pub fn new(
+        rates: Vec<f64>,
+        transitions: Vec<Vec<f64>>,
+        holding: Vec<Box<dyn Distribution>>,
+    ) -> Result<MarkovModulated, DistributionError> {
+        if rates.is_empty() {
+            return Err(DistributionError::EmptyComponents);
+        }
+        if transitions.len() != rates.len()
+            || holding.len() != rates.len()
+            || transitions.iter().any(|row| row.len() != rates.len())
+        {
+            return Err(DistributionError::DimensionMismatch);
+        }
+        if rates.iter().any(|rate| !rate.is_finite()) {
+            return Err(DistributionError::NonFiniteParameter);
+        }
+        if rates.iter().any(|rate| *rate < 0.0) {
+            return Err(DistributionError::NegativeValue);
+        }
+        if holding.iter().any(|holding| {
+            holding
+                .mean()
+                .is_some_and(|mean| mean.is_nan() || mean <= 0.0)
+        }) {
+            return Err(DistributionError::NonPositiveParameter);
+        }
+        if transitions.iter().any(|row| {
+            row.iter()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+                || row.iter().sum::<f64>() <= 0.0
+        }) {
+            return Err(DistributionError::InvalidWeights);
+        }
+        Ok(Self {
+            rates,
+            transitions,
+            holding,
+        })
+    }
+
+    /// Generate the arrival times in `[0, horizon)`, in increasing order. Returns `DistributionError::NoProgress` if a
+    /// holding time is zero or not a number, since the chain may then never reach `horizon`.
+    pub fn arrival_times(
+        &self,
+        rng: &mut dyn RngCore,
+        horizon: f64,
+    ) -> Result<Vec<f64>, DistributionError> {
+        let mut arrivals = Vec::new();
+        let mut state = 0;
+        let mut time = 0.0;
+        while time < horizon {
+            let holding = self.holding[state].sample(rng);
+            if holding.is_nan() || holding <= 0.0 {
+                return Err(DistributionError::NoProgress);
+            }
+            let leave = (time + holding).min(horizon);
+            let rate = self.rates[state];
+            if rate > 0.0 {
+                // Inter-arrival times are memoryless, so the stream can restart at every state change.
+                let mut arrival = time;
+                loop {
+                    let u: f64 = rng.gen();
+                    arrival += -(1.0 - u).ln() / rate;
+                    if arrival >= leave {
+                        break;
+                    }
+                    arrivals.push(arrival);
+                }
+            }
+            time = leave;
+            let row = &self.transitions[state];
+            let mut pick = rng.gen_range(0.0..row.iter().sum::<f64>());
+            state = row
+                .iter()
+                .position(|weight| {
+                    pick -= weight;
+                    pick < 0.0
+                })
+                .unwrap_or(row.len() - 1);
+        }
+        Ok(arrivals)
+    }
 }
@@ -3,18 +3,65 @@
 //! simulation. The [`Environment`] struct is responsible for running the simulation
 //! and storing the results.
 
-use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 
-use crate::distribution::Distribution;
-use std::cmp::Reverse;
-use std::collections::{BTreeMap, BinaryHeap, HashMap};
-use std::ops::{Generator, GeneratorState};
-use std::pin::Pin;
+use crate::distribution::{Distribution, DistributionError, MarkovModulated};
+use crate::threading::MaybeSend;
+use crate::time::{Clock, Time};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BinaryHeap, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+use core::ops::{Generator, GeneratorState};
+use core::pin::Pin;
 
 /// The type of process accepted by aika. Processes are generators that yields a value of type `T` and returns `()`.
+#[cfg(not(feature = "rayon"))]
 pub type Process<T> = Box<dyn Generator<Yield = T, Return = ()> + Unpin>;
+/// The type of process accepted by aika. Processes are generators that yields a value of type `T` and returns `()`.
+/// With the `rayon` feature, processes must be `Send` so that environments can be run on other threads.
+#[cfg(feature = "rayon")]
+pub type Process<T> = Box<dyn Generator<Yield = T, Return = ()> + Send + Unpin>;
+
+/// A process that runs a sequence of stages, each itself a process. Yields of the current stage are passed through
+/// to the environment, and the next stage starts on the resume after the current one completes. Completes once the
+/// last stage completes.
+pub struct CompositeProcess<T> {
+    stages: VecDeque<Process<T>>,
+}
+
+impl<T> CompositeProcess<T> {
+    /// Create a composite process running `stages` in order.
+    pub fn new(stages: Vec<Process<T>>) -> Self {
+        CompositeProcess {
+            stages: stages.into(),
+        }
+    }
+}
+
+impl<T> Generator for CompositeProcess<T> {
+    type Yield = T;
+    type Return = ();
+
+    fn resume(mut self: Pin<&mut Self>, _arg: ()) -> GeneratorState<T, ()> {
+        while let Some(stage) = self.stages.front_mut() {
+            match Pin::new(stage).resume(()) {
+                GeneratorState::Yielded(val) => return GeneratorState::Yielded(val),
+                GeneratorState::Complete(()) => {
+                    self.stages.pop_front();
+                }
+            }
+        }
+        GeneratorState::Complete(())
+    }
+}
 
 /// The type of function describing the event time delta for a given process. It can be constant, deterministic, or stochastic.
+#[derive(Clone)]
 pub enum ProcessExecution {
     /// Constant process execution. The process will execute at a constant time delta.
     Constant(u64),
@@ -22,9 +69,93 @@ pub enum ProcessExecution {
     Deterministic(fn(u64) -> u64),
     /// Stochastic process execution. The process will execute at a time delta given by the distribution.
     Stochastic(Box<dyn Distribution>),
+    /// Jittered process execution. The process will execute at a time delta of `period + sample - mean`, clamped to be
+    /// non-negative, where `mean` is the mean of the `jitter` distribution. Build it with [`ProcessExecution::jittered`],
+    /// which rejects distributions without a closed-form mean.
+    Jittered {
+        period: u64,
+        jitter: Box<dyn Distribution>,
+    },
+    /// Custom process execution. The process will execute at a time delta computed by the function from a read-only
+    /// [`StepContext`], so the delta can depend on the state of the simulation as well as the time.
+    Custom(Arc<DeltaFn>),
+}
+
+/// The function type of [`ProcessExecution::Custom`].
+#[cfg(not(feature = "rayon"))]
+pub type DeltaFn = dyn Fn(&mut StepContext<'_>) -> u64;
+/// The function type of [`ProcessExecution::Custom`].
+#[cfg(feature = "rayon")]
+pub type DeltaFn = dyn Fn(&mut StepContext<'_>) -> u64 + Send + Sync;
+
+/// A read-only view of the environment, passed to [`ProcessExecution::Custom`] when a process is about to run.
+pub struct StepContext<'a> {
+    /// The current event time in whole time units.
+    pub now: u64,
+    /// The number of events still queued, not counting the one being executed.
+    pub pending_events: usize,
+    /// The number of times the process has run before this event.
+    pub runs: u64,
+    /// The process's own random number stream.
+    pub rng: &'a mut dyn RngCore,
+}
+
+impl fmt::Debug for ProcessExecution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessExecution::Constant(delta) => f.debug_tuple("Constant").field(delta).finish(),
+            ProcessExecution::Deterministic(_) => write!(f, "Deterministic(..)"),
+            ProcessExecution::Stochastic(_) => write!(f, "Stochastic(..)"),
+            ProcessExecution::Custom(_) => write!(f, "Custom(..)"),
+            ProcessExecution::Jittered { period, .. } => f
+                .debug_struct("Jittered")
+                .field("period", period)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl ProcessExecution {
+    /// Constant process execution with the given period.
+    pub fn constant(period: u64) -> Self {
+        ProcessExecution::Constant(period)
+    }
+
+    /// Stochastic process execution drawing time deltas from `distribution`.
+    pub fn stochastic<D: Distribution + Clone + 'static>(distribution: D) -> Self {
+        ProcessExecution::Stochastic(Box::new(distribution))
+    }
+
+    /// Jittered process execution with the given period, centred by the mean of `jitter`. Returns
+    /// `DistributionError::NoMean` if `jitter` has no closed-form mean.
+    pub fn jittered<D: Distribution + Clone + 'static>(
+        period: u64,
+        jitter: D,
+    ) -> Result<Self, DistributionError> {
+        if jitter.mean().is_none() {
+            return Err(DistributionError::NoMean);
+        }
+        Ok(ProcessExecution::Jittered {
+            period,
+            jitter: Box::new(jitter),
+        })
+    }
+}
+
+impl From<u64> for ProcessExecution {
+    fn from(period: u64) -> Self {
+        ProcessExecution::Constant(period)
+    }
+}
+
+impl From<Box<dyn Distribution>> for ProcessExecution {
+    fn from(distribution: Box<dyn Distribution>) -> Self {
+        ProcessExecution::Stochastic(distribution)
+    }
 }
 
 /// The type of process duration. It can be standard, infinite, or finite.
+#[derive(Debug, Clone)]
 pub enum ProcessDuration {
     /// Standard process duration. The process will run from the first event time until the simulation is complete.
     Standard,
@@ -35,95 +166,459 @@ pub enum ProcessDuration {
 }
 
 /// The full process discription for the environment. It contains the process, the time delta, and the process duration.
-pub struct SimProcess<T> {
+/// Each process samples its stochastic time deltas from its own random number stream, so adding or removing one
+/// process does not perturb the draws of another.
+pub struct SimProcess<T, R = StdRng> {
     process: Process<T>,
     time_delta: ProcessExecution,
     process_duration: ProcessDuration,
+    rng: R,
 }
 
-impl<T> SimProcess<T> {
+impl<T, R> SimProcess<T, R> {
     fn new(
         process: Process<T>,
         time_delta: ProcessExecution,
         process_duration: ProcessDuration,
+        rng: R,
     ) -> Self {
         SimProcess {
-            process: process,
-            time_delta: time_delta,
-            process_duration: process_duration,
+            process,
+            time_delta,
+            process_duration,
+            rng,
         }
     }
 }
 
+/// Derive the random number stream of process `id` from the environment seed.
+fn process_rng<R: SeedableRng>(seed: u64, id: usize) -> R {
+    R::seed_from_u64(seed ^ (id as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// A function reading an observable value from the environment, used by [`Environment::add_monitor`].
+#[cfg(not(feature = "rayon"))]
+type Sampler<T, C, R> = Box<dyn FnMut(&Environment<T, C, R>) -> f64>;
+#[cfg(feature = "rayon")]
+type Sampler<T, C, R> = Box<dyn FnMut(&Environment<T, C, R>) -> f64 + Send>;
+
+/// A callback invoked after every resume, registered with [`Environment::set_on_step`].
+#[cfg(not(feature = "rayon"))]
+type StepHook<T, C> = Box<dyn FnMut(&Event<C>, Option<&T>)>;
+#[cfg(feature = "rayon")]
+type StepHook<T, C> = Box<dyn FnMut(&Event<C>, Option<&T>) + Send>;
+
+/// A sampler registered with [`Environment::add_monitor`], together with its period and next sample time. `next` is
+/// `None` once the next sample time would overflow the clock.
+struct Monitor<T, C: Time, R> {
+    period: u64,
+    next: Option<C>,
+    sampler: Sampler<T, C, R>,
+}
+
 /// Event struct. Contains information on which process to execute and when.
-pub struct Event {
+#[derive(Debug, Clone)]
+pub struct Event<C = u64> {
     /// The time at which the event occurs in the chain.
-    pub time: u64,
+    pub time: C,
     /// The id of the process to execute.
     pub process_id: usize,
+    /// Resolves ties between events at the same time: higher priorities execute first.
+    pub priority: u8,
+    /// Insertion counter used to break ties between events at the same time in FIFO order.
+    pub seq: u64,
 }
 
-impl Ord for Event {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.time.cmp(&other.time)
+/// Opaque handle to a scheduled event, used to cancel it with [`Environment::cancel_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventHandle(u64);
+
+impl<C: Ord> Ord for Event<C> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.time
+            .cmp(&other.time)
+            .then_with(|| other.priority.cmp(&self.priority))
+            .then_with(|| self.seq.cmp(&other.seq))
     }
 }
 
-impl PartialOrd for Event {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.time.cmp(&other.time))
+impl<C: Ord> PartialOrd for Event<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl PartialEq for Event {
+impl<C: Ord> PartialEq for Event<C> {
     fn eq(&self, other: &Self) -> bool {
-        self.time == other.time
+        self.time == other.time && self.priority == other.priority && self.seq == other.seq
     }
 }
 
-impl Eq for Event {}
+impl<C: Ord> Eq for Event<C> {}
+
+/// Execution statistics for a single process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessStats<C = u64> {
+    /// The number of times the process was resumed.
+    pub runs: u64,
+    /// The event time of the most recent resume.
+    pub last_time: C,
+}
+
+/// Outcome of a single call to [`Environment::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// An event was popped from the queue and resumed its process.
+    Executed,
+    /// An event was popped from the queue without resuming a process: it fell before a finite window opened, closed
+    /// the window, or belonged to a process that no longer exists.
+    Skipped,
+    /// The event queue was empty.
+    Empty,
+    /// The next event is scheduled after the maximum event time and was left in the queue.
+    ReachedStop,
+}
+
+/// Why a call to [`Environment::run`] or [`Environment::run_until`] returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The event queue was drained.
+    QueueEmpty,
+    /// The next queued event is after the requested time or the maximum event time.
+    ReachedStop,
+    /// The run was aborted after executing `max_events` events.
+    EventCap,
+}
+
+/// Summary of a call to [`Environment::run`] or [`Environment::run_until`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunReport<C = u64> {
+    /// The number of events executed during the call. Skipped events that resumed no process are not counted.
+    pub events_executed: usize,
+    /// The current event time when the call returned.
+    pub final_time: C,
+    /// Why the call returned.
+    pub stop_reason: StopReason,
+}
 
 /// The main struct of the library. It contains the `processes`, `events`, and `stores` of the
-/// simulation and keeps track of the current event time.
-pub struct Environment<T> {
+/// simulation and keeps track of the current event time. The clock `C` defaults to integer ticks, and the random number
+/// generator `R` used by the environment and each process stream defaults to `StdRng`. Pick e.g. `SmallRng` (behind
+/// `rand`'s `small_rng` feature) for speed, or a pinned algorithm such as `ChaCha20Rng` for reproducibility across
+/// `rand` versions.
+pub struct Environment<T, C: Time = u64, R = StdRng> {
     /// The events to be executed.
-    pub events: BinaryHeap<Reverse<Event>>,
+    pub events: BinaryHeap<Reverse<Event<C>>>,
     /// The processes and their id.
-    pub processes: HashMap<usize, SimProcess<T>>,
-    /// The current event time.
-    pub curr_event: u64,
+    pub processes: BTreeMap<usize, SimProcess<T, R>>,
+    /// The current event time. Read it with [`Environment::now`]; it only advances through stepping.
+    pub(crate) curr_event: C,
     /// The maximum event time.
-    pub max_event: u64,
+    pub max_event: C,
     /// The stores of the simulation yield.
-    pub stores: BTreeMap<u64, T>,
+    pub stores: BTreeMap<C, T>,
     /// Seeded random number generator for optional randomness.
-    pub rng: rand::rngs::StdRng,
+    pub rng: R,
+    /// The seed `rng` was created from.
+    pub seed: u64,
+    /// The sequence number assigned to the next scheduled event.
+    next_seq: u64,
+    /// The id assigned to the next added process. Ids are never reused, even after a process is removed.
+    next_id: usize,
+    /// The executed events, in execution order. Only recorded when `logs` is enabled.
+    pub past_events: Vec<Event<C>>,
+    /// Whether to record executed events into `past_events`.
+    pub logs: bool,
+    /// The ids of processes registered by name.
+    pub names: BTreeMap<String, usize>,
+    /// Execution statistics for each process that has been resumed at least once.
+    pub stats: BTreeMap<usize, ProcessStats<C>>,
+    /// Processes to schedule, with their delays, when a process's generator completes.
+    pub completion_hooks: BTreeMap<usize, Vec<(usize, u64)>>,
+    /// The `(time, value)` series recorded by each monitor, indexed by the id returned from [`Environment::add_monitor`].
+    pub monitors: Vec<Vec<(C, f64)>>,
+    samplers: Vec<Monitor<T, C, R>>,
+    on_step: Option<StepHook<T, C>>,
+    replaying: bool,
+    /// The random number streams of processes dropped by [`Environment::clone_without_processes`], by id, waiting
+    /// for the processes to be re-registered.
+    detached_rngs: BTreeMap<usize, R>,
+    /// Maps time units to real durations when set, e.g. to label plots.
+    pub clock: Option<Clock>,
+    /// The maximum number of events a single call to `run` or `run_until` may execute. Unlimited when `None`.
+    pub max_events: Option<usize>,
 }
 
-/// Implementation of the Environment struct. Contains public methods `new`, `add_process`, `run`.
 impl<T> Environment<T> {
+    /// Create an `Environment` running on integer ticks.
     pub fn new(max_event: u64, seed: u64) -> Self {
+        Self::with_clock(max_event, seed)
+    }
+}
+
+/// An `Environment` on integer ticks with no time limit, i.e. a `max_event` of `u64::MAX`, and a seed of `0`.
+impl<T> Default for Environment<T> {
+    fn default() -> Self {
+        Self::new(u64::MAX, 0)
+    }
+}
+
+/// Implementation of the Environment struct. Contains public methods `with_clock`, `add_process`, `run`.
+impl<T, C: Time, R: RngCore + SeedableRng> Environment<T, C, R> {
+    /// Create an `Environment` running on the clock `C` and drawing random numbers from `R`.
+    pub fn with_clock(max_event: C, seed: u64) -> Self {
         Environment {
             events: BinaryHeap::new(),
-            processes: HashMap::new(),
-            curr_event: 0,
-            max_event: max_event,
+            processes: BTreeMap::new(),
+            curr_event: C::default(),
+            max_event,
             stores: BTreeMap::new(),
-            rng: rand::rngs::StdRng::seed_from_u64(seed),
+            rng: R::seed_from_u64(seed),
+            seed,
+            next_seq: 0,
+            next_id: 0,
+            past_events: Vec::new(),
+            logs: false,
+            names: BTreeMap::new(),
+            stats: BTreeMap::new(),
+            completion_hooks: BTreeMap::new(),
+            monitors: Vec::new(),
+            samplers: Vec::new(),
+            on_step: None,
+            replaying: false,
+            detached_rngs: BTreeMap::new(),
+            clock: None,
+            max_events: None,
+        }
+    }
+
+    /// Copy the environment's state without its processes. Generators cannot be cloned, so the copy has no processes
+    /// and no monitors or step hook. Re-register the original processes with [`Environment::restore_process`] before
+    /// stepping it: each takes back its id along with a copy of its random number stream, and is not scheduled again
+    /// because the copied event queue already holds its events. Events for ids that are never restored are skipped,
+    /// and processes added with [`Environment::add_process`] get fresh ids. The event queue, logs, stores, statistics
+    /// and random number generators are copied, so as long as the restored generators behave like the originals, the
+    /// copy replays the same draws as the original.
+    pub fn clone_without_processes(&self) -> Environment<T, C, R>
+    where
+        T: Clone,
+        R: Clone,
+    {
+        Environment {
+            events: self.events.clone(),
+            processes: BTreeMap::new(),
+            curr_event: self.curr_event,
+            max_event: self.max_event,
+            stores: self.stores.clone(),
+            rng: self.rng.clone(),
+            seed: self.seed,
+            next_seq: self.next_seq,
+            next_id: self.next_id,
+            past_events: self.past_events.clone(),
+            logs: self.logs,
+            names: self.names.clone(),
+            stats: self.stats.clone(),
+            completion_hooks: self.completion_hooks.clone(),
+            monitors: Vec::new(),
+            samplers: Vec::new(),
+            on_step: None,
+            replaying: false,
+            detached_rngs: self
+                .processes
+                .iter()
+                .map(|(id, sim_process)| (*id, sim_process.rng.clone()))
+                .collect(),
+            clock: self.clock,
+            max_events: self.max_events,
+        }
+    }
+
+    /// The current event time, i.e. the time of the last executed event.
+    pub fn now(&self) -> C {
+        self.curr_event
+    }
+
+    /// Rewind the environment to time zero. Clears `events`, `past_events` and `stores`, and reseeds `rng` and the
+    /// process streams from `seed`. Processes that have not been removed stay registered, but generators keep their
+    /// internal state and are not rescheduled.
+    pub fn reset(&mut self) {
+        self.events.clear();
+        self.past_events.clear();
+        self.stores.clear();
+        self.stats.clear();
+        for (series, monitor) in self.monitors.iter_mut().zip(self.samplers.iter_mut()) {
+            series.clear();
+            monitor.next = Some(C::default());
+        }
+        self.curr_event = C::default();
+        self.next_seq = 0;
+        self.reseed(self.seed);
+    }
+
+    /// Replace `rng` and every process stream with fresh generators seeded from `seed`, and record it as the
+    /// environment's seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = R::seed_from_u64(seed);
+        for (id, sim_process) in self.processes.iter_mut() {
+            sim_process.rng = process_rng(seed, *id);
+        }
+        for (id, rng) in self.detached_rngs.iter_mut() {
+            *rng = process_rng(seed, *id);
         }
     }
 
-    /// Add a new process to the simulation environment.
+    /// Add a new process to the simulation environment. Returns the id assigned to the process.
     pub fn add_process(
         &mut self,
-        process: Box<dyn Generator<Yield = T, Return = ()> + Unpin>,
+        process: Process<T>,
         time_delta: ProcessExecution,
         process_duration: ProcessDuration,
-    ) {
-        let id = self.processes.len();
-        let process = SimProcess::new(process, time_delta, process_duration);
+    ) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        let process = SimProcess::new(
+            process,
+            time_delta,
+            process_duration,
+            process_rng(self.seed, id),
+        );
         self.processes.insert(id, process);
         self.init_process(id);
+        id
+    }
+
+    /// Add a new process to the simulation environment and register it under `name`. Returns the id assigned to the
+    /// process.
+    pub fn add_process_named(
+        &mut self,
+        name: &str,
+        process: Process<T>,
+        time_delta: ProcessExecution,
+        process_duration: ProcessDuration,
+    ) -> usize {
+        let id = self.add_process(process, time_delta, process_duration);
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    /// Re-register process `id` dropped by [`Environment::clone_without_processes`], giving it back the id and a copy
+    /// of the original random number stream. Events already queued for the process are kept rather than scheduled
+    /// again; a process with none queued that never ran is scheduled as in [`Environment::add_process`].
+    /// Returns `false` and registers nothing if `id` is not waiting to be restored.
+    pub fn restore_process(
+        &mut self,
+        id: usize,
+        process: Process<T>,
+        time_delta: ProcessExecution,
+        process_duration: ProcessDuration,
+    ) -> bool {
+        let Some(rng) = self.detached_rngs.remove(&id) else {
+            return false;
+        };
+        self.processes.insert(
+            id,
+            SimProcess::new(process, time_delta, process_duration, rng),
+        );
+        let queued = self
+            .events
+            .iter()
+            .any(|Reverse(event)| event.process_id == id);
+        if !queued && !self.stats.contains_key(&id) {
+            self.init_process(id);
+        }
+        true
+    }
+
+    /// Make process `id` recur every `period` time units from the absolute time `start`, up to and including `end` if
+    /// given, and schedule its first event at `start`. Events already queued for the process are dropped, so the
+    /// recurrence replaces its previous schedule.
+    /// Returns `None`, leaving the process unchanged, if it does not exist or `start` cannot be scheduled.
+    pub fn schedule_recurring(
+        &mut self,
+        id: usize,
+        period: u64,
+        start: u64,
+        end: Option<u64>,
+    ) -> Option<EventHandle> {
+        let first = C::from_ticks(start);
+        if first < self.curr_event || first > self.max_event {
+            return None;
+        }
+        let sim_process = self.processes.get_mut(&id)?;
+        sim_process.time_delta = ProcessExecution::Constant(period);
+        sim_process.process_duration = match end {
+            Some(end) => ProcessDuration::Finite(start, end.saturating_add(1)),
+            None => ProcessDuration::Infinite(start),
+        };
+        self.events.retain(|Reverse(event)| event.process_id != id);
+        self.add_event_at(id, C::from_ticks(start))
+    }
+
+    /// Call `hook` after every resume in [`Environment::step`] with the executed event and the yielded value, or
+    /// `None` if the generator completed. Replaces any previously set hook.
+    pub fn set_on_step<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Event<C>, Option<&T>) + MaybeSend + 'static,
+    {
+        self.on_step = Some(Box::new(hook));
+    }
+
+    /// Remove process `id`, its name registration and its queued events. Processes are also removed automatically
+    /// once their generator completes or their finite duration ends. Returns `false` if the process did not exist.
+    pub fn remove_process(&mut self, id: usize) -> bool {
+        if self.processes.remove(&id).is_none() {
+            return false;
+        }
+        self.names.retain(|_, named| *named != id);
+        self.events.retain(|Reverse(event)| event.process_id != id);
+        true
+    }
+
+    /// Schedule process `next` to run `delay` time units after the generator of process `id` completes.
+    pub fn on_complete(&mut self, id: usize, next: usize, delay: u64) {
+        self.completion_hooks
+            .entry(id)
+            .or_default()
+            .push((next, delay));
+    }
+
+    /// Sample `sampler` every `period` time units, starting at the current event time, and record each
+    /// `(time, value)` pair in `monitors`. A sample at time `t` sees the state after every event at or before `t`.
+    /// Sampling stops at the last executed event when a run returns. Returns the index of the monitor's series in
+    /// `monitors`.
+    pub fn add_monitor<F>(&mut self, period: u64, sampler: F) -> usize
+    where
+        F: FnMut(&Environment<T, C, R>) -> f64 + MaybeSend + 'static,
+    {
+        self.samplers.push(Monitor {
+            period: period.max(1),
+            next: Some(self.curr_event),
+            sampler: Box::new(sampler),
+        });
+        self.monitors.push(Vec::new());
+        self.monitors.len() - 1
+    }
+
+    /// Take every monitor sample that is due according to `due` and no later than `max_event`.
+    fn sample_monitors(&mut self, due: impl Fn(C) -> bool) {
+        let mut samplers = core::mem::take(&mut self.samplers);
+        for (id, monitor) in samplers.iter_mut().enumerate() {
+            while let Some(next) = monitor
+                .next
+                .filter(|next| *next <= self.max_event && due(*next))
+            {
+                let value = (monitor.sampler)(self);
+                self.monitors[id].push((next, value));
+                monitor.next = next.checked_add(C::from_ticks(monitor.period));
+            }
+        }
+        self.samplers = samplers;
+    }
+
+    /// Resolve the id of a process registered with [`Environment::add_process_named`].
+    pub fn process_id_by_name(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
     }
 
     /// Initialize a process by adding its first event to the event queue. Private function to be called in [`run`].
@@ -131,73 +626,480 @@ impl<T> Environment<T> {
         let process = self.processes.get(&id).unwrap();
         match process.process_duration {
             ProcessDuration::Standard => {
-                self.add_events(id, 0);
+                self.add_events(id, C::default());
             }
             ProcessDuration::Infinite(start) => {
-                self.add_events(id, start);
+                self.add_events(id, C::from_ticks(start));
             }
             ProcessDuration::Finite(start, _end) => {
-                self.add_events(id, start);
+                self.add_events(id, C::from_ticks(start));
             }
         }
     }
 
-    /// Execute the next event in the event queue the store the yield in stores.
-    fn step(&mut self) {
-        let event = self.events.pop().unwrap().0;
+    /// Execute the next event in the event queue and store the yield in stores.
+    /// Returns [`StepOutcome::Empty`] rather than panicking when there is nothing left to execute.
+    pub fn step(&mut self) -> StepOutcome {
+        let event = match self.events.peek() {
+            None => return StepOutcome::Empty,
+            Some(Reverse(next)) if next.time > self.max_event => return StepOutcome::ReachedStop,
+            Some(_) => self.events.pop().unwrap().0,
+        };
+        self.sample_monitors(|time| time < event.time);
         let process_id = event.process_id;
         self.curr_event = event.time;
-        let sim_process = self.processes.get_mut(&process_id).unwrap();
-        match sim_process.process_duration {
-            ProcessDuration::Finite(_start, end) => {
-                if self.curr_event >= end {
-                    return;
+        let Some(sim_process) = self.processes.get_mut(&process_id) else {
+            // The process was removed after the event was scheduled.
+            return StepOutcome::Skipped;
+        };
+        if let ProcessDuration::Finite(start, end) = sim_process.process_duration {
+            let (start, end) = (C::from_ticks(start), C::from_ticks(end));
+            if self.curr_event < start {
+                // Defer events scheduled before the process window opens, unless the process already has an event
+                // queued by then, which would resume it twice.
+                let queued = self
+                    .events
+                    .iter()
+                    .any(|Reverse(queued)| queued.process_id == process_id && queued.time <= start);
+                if !queued {
+                    self.add_event_at(process_id, start);
                 }
+                return StepOutcome::Skipped;
+            }
+            if self.curr_event >= end {
+                self.remove_process(process_id);
+                return StepOutcome::Skipped;
             }
-            _ => {}
         }
         let process = Pin::new(&mut sim_process.process);
-        let time_delta: u64;
-        match &sim_process.time_delta {
-            ProcessExecution::Constant(delta) => {
-                time_delta = *delta;
-            }
+        let time_delta = match &sim_process.time_delta {
+            ProcessExecution::Constant(delta) => C::from_ticks(*delta),
             ProcessExecution::Deterministic(events_path) => {
-                time_delta = events_path(self.curr_event);
+                C::from_ticks(events_path(self.curr_event.ticks()))
             }
             ProcessExecution::Stochastic(distribution_sample) => {
-                time_delta = distribution_sample.sample(&mut self.rng).round() as u64;
+                C::from_delta(distribution_sample.sample(&mut sim_process.rng))
             }
-        }
+            ProcessExecution::Jittered { period, jitter } => {
+                let mean = jitter.mean().unwrap_or(0.0);
+                let delta = *period as f64 + jitter.sample(&mut sim_process.rng) - mean;
+                C::from_delta(delta.max(0.0))
+            }
+            ProcessExecution::Custom(delta_fn) => C::from_ticks(delta_fn(&mut StepContext {
+                now: self.curr_event.ticks(),
+                pending_events: self.events.len(),
+                runs: self.stats.get(&process_id).map_or(0, |stats| stats.runs),
+                rng: &mut sim_process.rng,
+            })),
+        };
+        let stats = self.stats.entry(process_id).or_insert(ProcessStats {
+            runs: 0,
+            last_time: self.curr_event,
+        });
+        stats.runs += 1;
+        stats.last_time = self.curr_event;
         match process.resume(()) {
             GeneratorState::Yielded(val) => {
-                self.add_events(process_id, time_delta);
+                if let Some(hook) = &mut self.on_step {
+                    hook(&event, Some(&val));
+                }
+                if !self.replaying {
+                    self.add_events(process_id, time_delta);
+                }
                 self.stores.insert(self.curr_event, val);
-                self.curr_event += 1;
             }
-            GeneratorState::Complete(_output) => {}
+            GeneratorState::Complete(_output) => {
+                if let Some(hook) = &mut self.on_step {
+                    hook(&event, None);
+                }
+                self.remove_process(process_id);
+                // When replaying, the trace already contains the events the hooks scheduled.
+                let hooks = if self.replaying {
+                    None
+                } else {
+                    self.completion_hooks.get(&process_id).cloned()
+                };
+                for (next, delay) in hooks.into_iter().flatten() {
+                    self.add_events(next, C::from_ticks(delay));
+                }
+            }
+        }
+        if self.logs {
+            self.past_events.push(event);
         }
+        StepOutcome::Executed
     }
 
-    /// Run the simulation until the maximum event time is reached.
-    pub fn run(&mut self) {
-        if self.curr_event < self.max_event {
-            while !self.events.is_empty() {
-                self.step();
+    /// Run the simulation until the maximum event time is reached. No event scheduled after `max_event` is executed.
+    pub fn run(&mut self) -> RunReport<C> {
+        self.run_until(self.max_event)
+    }
+
+    /// Run the simulation, executing every event with a time of at most `until` and leaving later events queued.
+    /// Can be called repeatedly with increasing `until` to advance the simulation in stages.
+    /// Aborts with [`StopReason::EventCap`] once `max_events` events have executed in this call, not counting skipped
+    /// events.
+    pub fn run_until(&mut self, until: C) -> RunReport<C> {
+        let until = until.min(self.max_event);
+        let mut events_executed = 0;
+        let stop_reason = loop {
+            if !self.next_event_within(until) {
+                break if self.events.is_empty() {
+                    StopReason::QueueEmpty
+                } else {
+                    StopReason::ReachedStop
+                };
+            }
+            if self.max_events.is_some_and(|cap| events_executed >= cap) {
+                break StopReason::EventCap;
+            }
+            if self.step() == StepOutcome::Executed {
+                events_executed += 1;
             }
-        } else {
-            return;
+        };
+        // Sampling stops at the last executed event rather than filling the idle time up to `until` with identical
+        // samples. If the run continues, the next event takes the samples due before it.
+        if stop_reason != StopReason::EventCap {
+            let last_event = self.curr_event;
+            self.sample_monitors(|time| time <= last_event);
+        }
+        RunReport {
+            events_executed,
+            final_time: self.curr_event,
+            stop_reason,
         }
     }
 
-    /// Add an event to the event queue.
-    fn add_events(&mut self, id: usize, time_delta: u64) {
-        if self.curr_event + time_delta > self.max_event {
-            return;
+    /// Re-execute a recorded trace such as `past_events`: replace the event queue with the events of `trace` and run
+    /// them without scheduling any recurrences or completion hooks, so processes resume at exactly the recorded times
+    /// regardless of their `ProcessExecution`. Events before the current event time are skipped.
+    pub fn replay(&mut self, trace: &[Event<C>]) -> RunReport<C> {
+        self.events.clear();
+        for event in trace {
+            self.add_event_with_priority(event.process_id, event.time, event.priority);
+        }
+        self.replaying = true;
+        let report = self.run();
+        self.replaying = false;
+        report
+    }
+
+    /// Execute up to `n` events, stopping early if the event queue empties or the maximum event time is reached.
+    /// Skipped events are consumed but not counted. Returns the number of events actually executed.
+    pub fn step_n(&mut self, n: usize) -> usize {
+        let mut executed = 0;
+        while executed < n {
+            match self.step() {
+                StepOutcome::Executed => executed += 1,
+                StepOutcome::Skipped => {}
+                StepOutcome::Empty | StepOutcome::ReachedStop => break,
+            }
+        }
+        executed
+    }
+
+    /// Iterate over the simulation, executing one event per call to `next` and yielding its `(time, process_id)`.
+    /// Skipped events are consumed without being yielded. The iterator ends when the event queue empties or the next event is after the maximum event time.
+    pub fn events(&mut self) -> Stepper<'_, T, C, R> {
+        Stepper { env: self }
+    }
+
+    /// Project each event in `past_events` to a value with `sampler`, keyed by event time. When several events share
+    /// a time, the value of the last one executed is kept. Only covers events recorded while `logs` is enabled.
+    pub fn time_series_of<F: Fn(&Event<C>) -> f64>(&self, sampler: F) -> BTreeMap<C, f64> {
+        self.past_events
+            .iter()
+            .map(|event| (event.time, sampler(event)))
+            .collect()
+    }
+
+    /// Write `past_events` as CSV with a `time,process_id` header and one row per executed event. When `clock` is set,
+    /// a `real_seconds` column holds the real time of each event's whole time units. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn export_events_csv<W: std::io::Write>(&self, mut w: W) -> std::io::Result<()> {
+        match self.clock {
+            Some(clock) => {
+                writeln!(w, "time,process_id,real_seconds")?;
+                for event in &self.past_events {
+                    let real = clock.to_real(event.time.ticks()).as_secs_f64();
+                    writeln!(w, "{},{},{}", event.time, event.process_id, real)?;
+                }
+            }
+            None => {
+                writeln!(w, "time,process_id")?;
+                for event in &self.past_events {
+                    writeln!(w, "{},{}", event.time, event.process_id)?;
+                }
+            }
         }
+        Ok(())
+    }
+
+    /// Execution statistics for process `id`, or `None` if it has not been resumed yet.
+    pub fn process_stats(&self, id: usize) -> Option<&ProcessStats<C>> {
+        self.stats.get(&id)
+    }
+
+    /// Remove every queued event and return them in execution order, leaving the event queue empty.
+    pub fn drain_remaining(&mut self) -> Vec<Event<C>> {
+        core::iter::from_fn(|| self.events.pop().map(|Reverse(event)| event)).collect()
+    }
+
+    /// The number of events still queued.
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+
+    /// The number of executed events recorded in `past_events`. Only counts events while `logs` is enabled.
+    pub fn event_count(&self) -> usize {
+        self.past_events.len()
+    }
+
+    /// The time of the next queued event, without removing it. Returns `None` when the queue is empty.
+    pub fn peek_next_time(&self) -> Option<C> {
+        self.events.peek().map(|Reverse(next)| next.time)
+    }
+
+    /// Whether the next queued event is scheduled at or before `until`.
+    fn next_event_within(&self, until: C) -> bool {
+        matches!(self.events.peek(), Some(Reverse(next)) if next.time <= until)
+    }
+
+    /// Add an event for process `id` to the event queue, `time_delta` after the current event time.
+    /// Returns a handle for cancelling the event, or `None` if it falls after the maximum event time and was dropped.
+    /// A delta too large for the clock to represent counts as falling after the maximum event time.
+    pub fn add_events(&mut self, id: usize, time_delta: C) -> Option<EventHandle> {
+        let time = self.curr_event.checked_add(time_delta)?;
+        self.add_event_at(id, time)
+    }
+
+    /// Add an event for process `id` to the event queue at the absolute `time`.
+    /// Returns `None` and schedules nothing if `time` is before the current event time or after the maximum event time.
+    pub fn add_event_at(&mut self, id: usize, time: C) -> Option<EventHandle> {
+        self.add_event_with_priority(id, time, 0)
+    }
+
+    /// Add an event for process `id` at the absolute `time`, executing before events at the same time with a lower
+    /// `priority`. Events added without a priority, including those processes schedule for themselves, have priority `0`.
+    pub fn add_event_with_priority(
+        &mut self,
+        id: usize,
+        time: C,
+        priority: u8,
+    ) -> Option<EventHandle> {
+        if time < self.curr_event || time > self.max_event {
+            return None;
+        }
+        let seq = self.next_seq;
         self.events.push(Reverse(Event {
-            time: self.curr_event + time_delta,
+            time,
             process_id: id,
+            priority,
+            seq,
         }));
+        self.next_seq += 1;
+        Some(EventHandle(seq))
+    }
+
+    /// Schedule process `id` at the current event time, e.g. to feed external input into a simulation paused between
+    /// calls to [`Environment::run_until`]. The event runs after any events already queued for the same time.
+    pub fn inject(&mut self, id: usize) -> Option<EventHandle> {
+        self.add_event_at(id, self.curr_event)
+    }
+
+    /// Add one event for process `id` at each absolute time in `times`. Times outside the schedulable window are skipped.
+    pub fn seed_schedule(&mut self, id: usize, times: &[C]) {
+        for time in times {
+            self.add_event_at(id, *time);
+        }
+    }
+
+    /// Precompute an arrival stream for process `id`: starting at the current event time, add successive deltas drawn
+    /// from `distribution` with `rng` and schedule an event at each arrival up to and including `until`. Every arrival
+    /// resumes the process on top of the events it schedules for itself, so pair this with an execution delta that
+    /// falls past `max_event`. Returns the number of arrivals scheduled, or `DistributionError::NoProgress` if a drawn
+    /// delta is zero or not a number, since such a stream may never reach `until`. Arrivals scheduled before the error
+    /// stay queued.
+    pub fn generate_arrivals(
+        &mut self,
+        id: usize,
+        distribution: &dyn Distribution,
+        until: C,
+    ) -> Result<usize, DistributionError> {
+        let mut elapsed = 0.0;
+        let mut arrivals = 0;
+        loop {
+            let next = elapsed + distribution.sample(&mut self.rng);
+            if next.is_nan() || next <= elapsed {
+                return Err(DistributionError::NoProgress);
+            }
+            elapsed = next;
+            match self.curr_event.checked_add(C::from_delta(elapsed)) {
+                Some(time) if time <= until => {
+                    if self.add_event_at(id, time).is_some() {
+                        arrivals += 1;
+                    }
+                }
+                _ => return Ok(arrivals),
+            }
+        }
+    }
+
+    /// Precompute a bursty arrival stream for process `id` from a Markov-modulated Poisson process, starting at the
+    /// current event time, drawing from `rng` and scheduling arrivals up to and including `until`. Like
+    /// [`Environment::generate_arrivals`], every arrival resumes the process on top of the events it schedules for
+    /// itself. Returns the number of arrivals scheduled, or `DistributionError::NoProgress` if a holding time is zero
+    /// or not a number, in which case nothing is scheduled.
+    pub fn generate_modulated_arrivals(
+        &mut self,
+        id: usize,
+        arrivals: &MarkovModulated,
+        until: C,
+    ) -> Result<usize, DistributionError> {
+        let horizon = until.ticks().saturating_sub(self.curr_event.ticks()) as f64 + 1.0;
+        let mut scheduled = 0;
+        for offset in arrivals.arrival_times(&mut self.rng, horizon)? {
+            match self.curr_event.checked_add(C::from_delta(offset)) {
+                Some(time) if time <= until => {
+                    if self.add_event_at(id, time).is_some() {
+                        scheduled += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(scheduled)
+    }
+
+    /// Remove a pending event from the event queue. Returns `false` if the event already executed or was cancelled.
+    pub fn cancel_event(&mut self, handle: EventHandle) -> bool {
+        let pending = self.events.len();
+        self.events.retain(|Reverse(event)| event.seq != handle.0);
+        self.events.len() < pending
+    }
+}
+
+/// Iterator returned by [`Environment::events`], advancing the simulation lazily one event at a time.
+pub struct Stepper<'a, T, C: Time = u64, R = StdRng> {
+    env: &'a mut Environment<T, C, R>,
+}
+
+impl<'a, T, C: Time, R: RngCore + SeedableRng> Iterator for Stepper<'a, T, C, R> {
+    type Item = (C, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let process_id = self.env.events.peek()?.0.process_id;
+            match self.env.step() {
+                StepOutcome::Executed => return Some((self.env.now(), process_id)),
+                StepOutcome::Skipped => {}
+                StepOutcome::Empty | StepOutcome::ReachedStop => return None,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "plot")]
+impl<T> Environment<T> {
+    /// Draw the series recorded by monitor `id` as a step chart and save it as a PNG image at `path`. Axis labels need a
+    /// font registered with [`register_plot_font`] first; without one the font lookup error is returned.
+    pub fn plot_monitor(&self, id: usize, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        use plotters::prelude::*;
+
+        let series = self.monitors.get(id).ok_or("no monitor with this id")?;
+        let x_max = series.last().map_or(0, |(time, _)| *time).max(1);
+        let (y_min, y_max) = series
+            .iter()
+            .fold((0.0_f64, 1.0_f64), |(low, high), (_, value)| {
+                (low.min(*value), high.max(*value))
+            });
+        let root = BitMapBackend::new(path, (800, 600)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(0..x_max, y_min..y_max)?;
+        let x_desc = match self.clock {
+            Some(clock) => format!("time (units of {:?})", clock.tick_duration()),
+            None => "time".to_string(),
+        };
+        chart.configure_mesh().x_desc(x_desc).draw()?;
+        // Hold each sample until the next one to draw a step rather than a linear interpolation.
+        let steps = series
+            .windows(2)
+            .flat_map(|pair| [pair[0], (pair[1].0, pair[0].1)])
+            .chain(series.last().copied());
+        chart.draw_series(LineSeries::new(steps, &BLUE))?;
+        root.present()?;
+        Ok(())
+    }
+}
+
+/// Register the font used for the labels of [`Environment::plot_monitor`], from the complete contents of a TrueType
+/// or OpenType font file such as `include_bytes!("DejaVuSans.ttf")`. No font ships with aika, which keeps the `plot`
+/// feature free of system font libraries.
+#[cfg(feature = "plot")]
+pub fn register_plot_font(bytes: &'static [u8]) -> Result<(), Box<dyn std::error::Error>> {
+    use plotters::style::{register_font, FontStyle};
+
+    register_font("sans-serif", FontStyle::Normal, bytes)
+        .map_err(|_| "not a valid font file".into())
+}
+
+/// Builder for an [`Environment`]. Defaults to a maximum event time of `u64::MAX` and a seed of `0`, like
+/// `Environment::default()`.
+pub struct EnvironmentBuilder<T> {
+    max_event: u64,
+    seed: u64,
+    processes: Vec<(Process<T>, ProcessExecution, ProcessDuration)>,
+}
+
+impl<T> Default for EnvironmentBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EnvironmentBuilder<T> {
+    /// Create a new `EnvironmentBuilder` with no processes.
+    pub fn new() -> Self {
+        EnvironmentBuilder {
+            max_event: u64::MAX,
+            seed: 0,
+            processes: Vec::new(),
+        }
+    }
+
+    /// Set the maximum event time of the simulation.
+    pub fn max_event(mut self, max_event: u64) -> Self {
+        self.max_event = max_event;
+        self
+    }
+
+    /// Set the seed of the environment's random number generator.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Add a process. Processes are registered in the order they are added, so the first gets id `0`.
+    pub fn process(
+        mut self,
+        process: Process<T>,
+        time_delta: ProcessExecution,
+        process_duration: ProcessDuration,
+    ) -> Self {
+        self.processes.push((process, time_delta, process_duration));
+        self
+    }
+
+    /// Build the configured `Environment`.
+    pub fn build(self) -> Environment<T> {
+        let mut env = Environment::new(self.max_event, self.seed);
+        for (process, time_delta, process_duration) in self.processes {
+            env.add_process(process, time_delta, process_duration);
+        }
+        env
     }
 }
@@ -1,13 +1,23 @@
 #![feature(generators, generator_trait)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 pub mod distribution;
 pub mod environment;
 pub mod manager;
+pub mod stats;
+pub mod threading;
+pub mod time;
 
 #[cfg(test)]
 mod test {
     use super::distribution::*;
     use super::environment::*;
+    use super::manager::*;
+    use super::stats::*;
+    use super::time::*;
+    use std::cmp::Reverse;
 
     #[test]
     fn setup_simple_des() {
@@ -22,7 +32,7 @@ mod test {
             }
         });
         // Execution Distribution
-        let gamma = Gamma::new(7.0, 1.0);
+        let gamma = Gamma::new(7.0, 1.0).unwrap();
 
         env.add_process(
             process_random,
@@ -32,4 +42,2131 @@ mod test {
         env.run();
         println!("{:?}", env.stores);
     }
+
+    #[test]
+    fn uniform_samples_within_bounds() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let uniform = Uniform::new(1.0, 5.0).unwrap();
+        for _ in 0..10_000 {
+            let sample = uniform.sample(&mut rng);
+            assert!((1.0..5.0).contains(&sample));
+        }
+        assert_eq!(
+            Uniform::new(-1.0, 5.0).err(),
+            Some(DistributionError::NegativeBound)
+        );
+        assert_eq!(
+            Uniform::new(5.0, 5.0).err(),
+            Some(DistributionError::EmptyRange)
+        );
+        assert_eq!(
+            Uniform::new(1.0, f64::INFINITY).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+        assert_eq!(
+            Uniform::new(f64::NAN, 5.0).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+    }
+
+    #[test]
+    fn empirical_samples_from_data() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let data = vec![1.0, 2.5, 4.0];
+        let empirical = Empirical::new(data.clone()).unwrap();
+        for _ in 0..1_000 {
+            assert!(data.contains(&empirical.sample(&mut rng)));
+        }
+        assert_eq!(
+            Empirical::new(vec![]).err(),
+            Some(DistributionError::EmptySamples)
+        );
+        assert_eq!(
+            Empirical::new(vec![1.0, -2.0]).err(),
+            Some(DistributionError::NegativeSample)
+        );
+        assert_eq!(
+            Empirical::new(vec![1.0, f64::NAN]).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+    }
+
+    #[test]
+    fn sample_n_matches_repeated_sampling() {
+        use rand::SeedableRng;
+        let gamma = Gamma::new(7.0, 1.0).unwrap();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let batch = gamma.sample_n(&mut rng, 50);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let looped: Vec<f64> = (0..50).map(|_| gamma.sample(&mut rng)).collect();
+        assert_eq!(batch.len(), 50);
+        assert_eq!(batch, looped);
+    }
+
+    #[test]
+    fn deterministic_dist_is_constant() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let fixed = DeterministicDist::new(4.0).unwrap();
+        let samples = fixed.sample_n(&mut rng, 100);
+        assert!(samples.iter().all(|sample| *sample == 4.0));
+        assert_eq!(
+            DeterministicDist::new(-1.0).err(),
+            Some(DistributionError::NegativeValue)
+        );
+        assert_eq!(
+            DeterministicDist::new(f64::NAN).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+    }
+
+    #[test]
+    fn truncated_bounds_and_clamps() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let truncated = Truncated::new(Gamma::new(2.0, 3.0).unwrap(), 1.0, 4.0).unwrap();
+        for sample in truncated.sample_n(&mut rng, 1_000) {
+            assert!((1.0..=4.0).contains(&sample));
+        }
+
+        // A distribution that never lands in range is clamped once retries run out.
+        let exhausted = Truncated::new(DeterministicDist::new(10.0).unwrap(), 0.0, 5.0)
+            .unwrap()
+            .with_max_retries(3);
+        assert_eq!(exhausted.sample(&mut rng), 5.0);
+        assert_eq!(
+            Truncated::new(Gamma::new(2.0, 3.0).unwrap(), f64::NAN, 4.0).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+        assert_eq!(
+            Truncated::new(Gamma::new(2.0, 3.0).unwrap(), 1.0, f64::INFINITY).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+
+        let mut rng_a = rand::rngs::StdRng::seed_from_u64(9);
+        let mut rng_b = rand::rngs::StdRng::seed_from_u64(9);
+        assert_eq!(
+            truncated.sample_n(&mut rng_a, 100),
+            truncated.sample_n(&mut rng_b, 100)
+        );
+    }
+
+    #[test]
+    fn cloned_distribution_samples_identically() {
+        use rand::SeedableRng;
+        let boxed: Box<dyn Distribution> = Box::new(Gamma::new(7.0, 1.0).unwrap());
+        let cloned = boxed.clone();
+        let mut rng_a = rand::rngs::StdRng::seed_from_u64(17);
+        let mut rng_b = rand::rngs::StdRng::seed_from_u64(17);
+        assert_eq!(
+            boxed.sample_n(&mut rng_a, 100),
+            cloned.sample_n(&mut rng_b, 100)
+        );
+
+        let execution = ProcessExecution::Stochastic(boxed);
+        assert!(matches!(execution.clone(), ProcessExecution::Stochastic(_)));
+    }
+
+    #[test]
+    fn half_normal_is_non_negative() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(21);
+        let half_normal = HalfNormal::new(3.0).unwrap();
+        assert!(half_normal
+            .sample_n(&mut rng, 50_000)
+            .iter()
+            .all(|sample| *sample >= 0.0));
+        assert_eq!(
+            HalfNormal::new(0.0).err(),
+            Some(DistributionError::NonPositiveParameter)
+        );
+        assert_eq!(
+            HalfNormal::new(f64::INFINITY).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+    }
+
+    #[test]
+    fn mixture_picks_components_by_weight() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(31);
+        let mixture = Mixture::new(vec![
+            (1.0, Box::new(DeterministicDist::new(1.0).unwrap())),
+            (1.0, Box::new(DeterministicDist::new(9.0).unwrap())),
+        ])
+        .unwrap();
+        let samples = mixture.sample_n(&mut rng, 10_000);
+        let fast = samples.iter().filter(|sample| **sample == 1.0).count();
+        let slow = samples.iter().filter(|sample| **sample == 9.0).count();
+        assert_eq!(fast + slow, 10_000);
+        assert!((4_500..5_500).contains(&fast));
+
+        assert_eq!(
+            Mixture::new(vec![]).err(),
+            Some(DistributionError::EmptyComponents)
+        );
+        assert_eq!(
+            Mixture::new(vec![(-1.0, Box::new(DeterministicDist::new(1.0).unwrap()))]).err(),
+            Some(DistributionError::InvalidWeights)
+        );
+        assert_eq!(
+            Mixture::new(vec![(
+                f64::INFINITY,
+                Box::new(DeterministicDist::new(1.0).unwrap())
+            )])
+            .err(),
+            Some(DistributionError::InvalidWeights)
+        );
+    }
+
+    #[test]
+    fn run_never_executes_past_max_event() {
+        let mut env = Environment::new(100, 0);
+        let process = Box::new(move || loop {
+            yield 1;
+        });
+        env.add_process(
+            process,
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        env.events.push(Reverse(Event {
+            time: 110,
+            process_id: 0,
+            priority: 0,
+            seq: 0,
+        }));
+        env.run();
+        assert!(env.stores.is_empty());
+        assert_eq!(env.events.len(), 1);
+    }
+
+    #[test]
+    fn run_until_resumes_to_same_result() {
+        let build = || {
+            let mut env = Environment::new(100, 42);
+            let process = Box::new(move || {
+                let mut i = 0;
+                loop {
+                    yield i;
+                    i += 1;
+                }
+            });
+            env.add_process(
+                process,
+                ProcessExecution::Stochastic(Box::new(Gamma::new(3.0, 2.0).unwrap())),
+                ProcessDuration::Standard,
+            );
+            env
+        };
+        let mut full = build();
+        full.run();
+
+        let mut staged = build();
+        staged.run_until(50);
+        assert!(staged.now() <= 50);
+        assert!(staged.events.peek().unwrap().0.time > 50);
+        staged.run_until(100);
+        assert_eq!(staged.stores, full.stores);
+        assert_eq!(staged.now(), full.now());
+    }
+
+    #[test]
+    fn step_n_reports_events_executed() {
+        let mut env = Environment::new(100, 0);
+        let process = Box::new(move || loop {
+            yield 1;
+        });
+        env.add_process(
+            process,
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        for time in [10, 20] {
+            env.events.push(Reverse(Event {
+                time,
+                process_id: 0,
+                priority: 0,
+                seq: time,
+            }));
+        }
+        assert_eq!(env.step_n(3), 2);
+        assert_eq!(env.step_n(3), 0);
+        assert_eq!(env.stores.len(), 2);
+    }
+
+    #[test]
+    fn step_on_empty_queue_does_not_panic() {
+        let mut env: Environment<i32> = Environment::new(100, 0);
+        assert_eq!(env.step(), StepOutcome::Empty);
+
+        env.events.push(Reverse(Event {
+            time: 150,
+            process_id: 0,
+            priority: 0,
+            seq: 0,
+        }));
+        assert_eq!(env.step(), StepOutcome::ReachedStop);
+        assert_eq!(env.events.len(), 1);
+    }
+
+    #[test]
+    fn same_time_events_run_in_insertion_order() {
+        use std::sync::{Arc, Mutex};
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut env = Environment::new(50, 0);
+        for id in 0..3 {
+            let order = order.clone();
+            env.add_process(
+                Box::new(move || loop {
+                    order.lock().unwrap().push(id);
+                    yield id;
+                }),
+                ProcessExecution::Constant(10),
+                ProcessDuration::Standard,
+            );
+        }
+        env.run();
+        let expected: Vec<i32> = (0..6).flat_map(|_| 0..3).collect();
+        assert_eq!(*order.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn cancelled_event_never_resumes() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        let kept = env.add_events(0, 20).unwrap();
+        let cancelled = env.add_events(0, 10).unwrap();
+        assert!(env.add_events(0, 500).is_none());
+        assert!(env.cancel_event(cancelled));
+        assert!(!env.cancel_event(cancelled));
+        env.run();
+        assert_eq!(env.stores.keys().copied().collect::<Vec<_>>(), vec![20]);
+        assert!(!env.cancel_event(kept));
+    }
+
+    #[test]
+    fn logs_record_executed_events() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let resumes = Arc::new(AtomicUsize::new(0));
+        let mut env = Environment::new(100, 1);
+        env.logs = true;
+        for _ in 0..2 {
+            let resumes = resumes.clone();
+            env.add_process(
+                Box::new(move || loop {
+                    resumes.fetch_add(1, Ordering::SeqCst);
+                    yield 0;
+                }),
+                ProcessExecution::Stochastic(Box::new(Gamma::new(7.0, 1.0).unwrap())),
+                ProcessDuration::Standard,
+            );
+        }
+        env.run();
+        assert_eq!(env.past_events.len(), resumes.load(Ordering::SeqCst));
+        assert!(env
+            .past_events
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time));
+    }
+
+    #[test]
+    fn peek_next_time_matches_step() {
+        let mut env = Environment::new(100, 4);
+        assert_eq!(env.peek_next_time(), None);
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Stochastic(Box::new(Gamma::new(7.0, 1.0).unwrap())),
+            ProcessDuration::Infinite(5),
+        );
+        while let Some(next) = env.peek_next_time() {
+            assert_eq!(env.step(), StepOutcome::Executed);
+            assert_eq!(env.now(), next);
+        }
+        assert_eq!(env.step(), StepOutcome::Empty);
+    }
+
+    #[test]
+    fn named_processes_resolve_to_ids() {
+        let mut env = Environment::new(100, 0);
+        for (name, value) in [("arrivals", 1), ("departures", 2)] {
+            env.add_process_named(
+                name,
+                Box::new(move || loop {
+                    yield value;
+                }),
+                ProcessExecution::Constant(1000),
+                ProcessDuration::Infinite(200),
+            );
+        }
+        assert_eq!(env.process_id_by_name("arrivals"), Some(0));
+        assert_eq!(env.process_id_by_name("missing"), None);
+        let departures = env.process_id_by_name("departures").unwrap();
+        env.add_events(departures, 30);
+        env.run();
+        assert_eq!(env.stores.get(&30), Some(&2));
+    }
+
+    #[test]
+    fn yield_schedules_single_follow_up() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        assert_eq!(env.events.len(), 1);
+        for _ in 0..5 {
+            assert_eq!(env.step(), StepOutcome::Executed);
+            assert_eq!(env.events.len(), 1);
+        }
+    }
+
+    #[test]
+    fn finite_process_waits_for_start() {
+        let mut env = Environment::new(200, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(5),
+            ProcessDuration::Finite(50, 70),
+        );
+        // An early event is dropped in favour of the first event at the start of the window rather than executed at
+        // tick 10 or doubling every later recurrence.
+        env.add_events(0, 10);
+        env.run();
+        assert_eq!(
+            env.past_events
+                .iter()
+                .map(|event| event.time)
+                .collect::<Vec<_>>(),
+            vec![50, 55, 60, 65]
+        );
+    }
+
+    #[test]
+    fn reset_rewinds_clock_and_queues() {
+        use rand::Rng;
+        let mut env = Environment::new(100, 8);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Infinite(5),
+        );
+        let first_draw: u64 = env.rng.clone().gen();
+        env.run_until(50);
+        env.reset();
+        assert_eq!(env.now(), 0);
+        assert!(env.events.is_empty());
+        assert!(env.past_events.is_empty());
+        assert!(env.stores.is_empty());
+        assert_eq!(env.rng.gen::<u64>(), first_draw);
+    }
+
+    #[test]
+    fn reseed_reproduces_stochastic_deltas() {
+        let build = |seed| {
+            let mut env = Environment::new(500, 0);
+            env.reseed(seed);
+            env.logs = true;
+            env.add_process(
+                Box::new(move || loop {
+                    yield 0;
+                }),
+                ProcessExecution::Stochastic(Box::new(Gamma::new(7.0, 1.0).unwrap())),
+                ProcessDuration::Standard,
+            );
+            env.run();
+            env.past_events
+                .iter()
+                .map(|event| event.time)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(build(99), build(99));
+        assert_ne!(build(99), build(100));
+
+        let mut env: Environment<i32> = Environment::new(100, 1);
+        env.reseed(5);
+        assert_eq!(env.seed, 5);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_run_matches_sequential() {
+        let build = |seed| {
+            let mut env = Environment::new(200, seed);
+            env.add_process(
+                Box::new(move || {
+                    let mut i = 0;
+                    loop {
+                        yield i;
+                        i += 1;
+                    }
+                }),
+                ProcessExecution::Stochastic(Box::new(Gamma::new(3.0, 2.0).unwrap())),
+                ProcessDuration::Standard,
+            );
+            env
+        };
+        let mut sequential = Manager::new();
+        let mut parallel = Manager::new();
+        for seed in 0..8 {
+            sequential.add_simulation(build(seed));
+            parallel.add_simulation(build(seed));
+        }
+        sequential.run();
+        parallel.run_parallel();
+        assert_eq!(sequential.stores, parallel.stores);
+    }
+
+    #[test]
+    fn replications_are_distinct_and_reproducible() {
+        let builder = |seed| {
+            let mut env = Environment::new(200, seed);
+            env.add_process(
+                Box::new(move || {
+                    let mut i = 0;
+                    loop {
+                        yield i;
+                        i += 1;
+                    }
+                }),
+                ProcessExecution::Stochastic(Box::new(Gamma::new(3.0, 2.0).unwrap())),
+                ProcessDuration::Standard,
+            );
+            env
+        };
+        let mut first = Manager::new();
+        first.replicate(builder, 2, 10);
+        first.run();
+        let mut second = Manager::new();
+        second.replicate(builder, 2, 10);
+        second.run();
+        assert_eq!(first.simulations[1].seed, 11);
+        assert_ne!(first.stores[0], first.stores[1]);
+        assert_eq!(first.stores, second.stores);
+    }
+
+    #[test]
+    fn summary_of_constant_replications() {
+        let mut manager = Manager::new();
+        manager.replicate(
+            |seed| {
+                let mut env = Environment::new(50, seed);
+                env.add_process(
+                    Box::new(move || loop {
+                        yield 4.0;
+                    }),
+                    ProcessExecution::Constant(10),
+                    ProcessDuration::Standard,
+                );
+                env
+            },
+            3,
+            0,
+        );
+        manager.run();
+        let summary = manager.summary();
+        assert_eq!(summary.len(), 6);
+        for stats in summary.values() {
+            assert_eq!(stats.n, 3);
+            assert_eq!(stats.mean, 4.0);
+            assert_eq!(stats.variance, 0.0);
+            assert_eq!(stats.ci95, (4.0, 4.0));
+        }
+    }
+
+    #[test]
+    fn manager_runs_f64_simulations() {
+        let mut manager: Manager<f64> = Manager::new();
+        for seed in 0..2 {
+            let mut env = Environment::new(100, seed);
+            env.add_process(
+                Box::new(move || {
+                    let mut level = 0.0;
+                    loop {
+                        yield level;
+                        level += 0.5;
+                    }
+                }),
+                ProcessExecution::Stochastic(Box::new(Gamma::new(7.0, 1.0).unwrap())),
+                ProcessDuration::Standard,
+            );
+            manager.add_simulation(env);
+        }
+        manager.run();
+        assert_eq!(manager.stores.len(), 2);
+        for (store, simulation) in manager.stores.iter().zip(&manager.simulations) {
+            assert!(!store.is_empty());
+            assert_eq!(store, &simulation.stores);
+        }
+    }
+
+    #[test]
+    fn progress_callback_fires_per_simulation() {
+        let mut manager: Manager<i32> = Manager::new();
+        manager.replicate(|seed| Environment::new(10, seed), 4, 0);
+        let mut calls = Vec::new();
+        manager.run_with_progress(|completed, total| calls.push((completed, total)));
+        assert_eq!(calls, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    #[test]
+    fn now_tracks_last_executed_event() {
+        let mut env = Environment::new(100, 2);
+        env.logs = true;
+        assert_eq!(env.now(), 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Stochastic(Box::new(Gamma::new(7.0, 1.0).unwrap())),
+            ProcessDuration::Infinite(3),
+        );
+        env.run();
+        assert_eq!(env.now(), env.past_events.last().unwrap().time);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn export_events_csv_writes_trace() {
+        let mut env = Environment::new(30, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        env.run();
+        let mut csv = Vec::new();
+        env.export_events_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time,process_id\n0,0\n10,0\n20,0\n30,0\n"
+        );
+    }
+
+    #[test]
+    fn zero_delay_reschedules_same_tick() {
+        let mut env = Environment::new(10, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || {
+                for i in 0..3 {
+                    yield i;
+                }
+            }),
+            ProcessExecution::Constant(0),
+            ProcessDuration::Infinite(5),
+        );
+        env.add_process(
+            Box::new(move || loop {
+                yield 10;
+            }),
+            ProcessExecution::Constant(100),
+            ProcessDuration::Infinite(5),
+        );
+        env.run();
+        let trace: Vec<(u64, usize)> = env
+            .past_events
+            .iter()
+            .map(|event| (event.time, event.process_id))
+            .collect();
+        // The zero-delay follow-up runs after the other process already queued for tick 5.
+        assert_eq!(trace, vec![(5, 0), (5, 1), (5, 0), (5, 0), (5, 0)]);
+    }
+
+    #[test]
+    fn builder_configures_environment() {
+        let mut env = EnvironmentBuilder::new()
+            .max_event(50)
+            .seed(12)
+            .process(
+                Box::new(move || loop {
+                    yield 1;
+                }),
+                ProcessExecution::Constant(10),
+                ProcessDuration::Standard,
+            )
+            .process(
+                Box::new(move || loop {
+                    yield 2;
+                }),
+                ProcessExecution::Constant(10),
+                ProcessDuration::Infinite(5),
+            )
+            .build();
+        assert_eq!(env.max_event, 50);
+        assert_eq!(env.seed, 12);
+        let default: Environment<u64> = EnvironmentBuilder::new().build();
+        assert_eq!(default.max_event, Environment::<u64>::default().max_event);
+        assert_eq!(env.processes.len(), 2);
+        env.run();
+        assert_eq!(env.stores.get(&40), Some(&1));
+        assert_eq!(env.stores.get(&45), Some(&2));
+    }
+
+    #[test]
+    fn add_event_at_schedules_absolute_times() {
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        for value in [1, 2] {
+            env.add_process(
+                Box::new(move || loop {
+                    yield value;
+                }),
+                ProcessExecution::Constant(1000),
+                ProcessDuration::Infinite(200),
+            );
+        }
+        assert!(env.add_event_at(1, 30).is_some());
+        assert!(env.add_event_at(0, 10).is_some());
+        assert!(env.add_event_at(0, 101).is_none());
+        env.run_until(20);
+        assert!(env.add_event_at(0, 5).is_none());
+        env.run();
+        let trace: Vec<(u64, usize)> = env
+            .past_events
+            .iter()
+            .map(|event| (event.time, event.process_id))
+            .collect();
+        assert_eq!(trace, vec![(10, 0), (30, 1)]);
+    }
+
+    #[test]
+    fn seed_schedule_replays_arrivals() {
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        env.seed_schedule(0, &[3, 17, 17, 42, 99, 150]);
+        env.run();
+        let times: Vec<u64> = env.past_events.iter().map(|event| event.time).collect();
+        assert_eq!(times, vec![3, 17, 17, 42, 99]);
+    }
+
+    #[test]
+    fn fixed_point_clock_schedules_fractional_deltas() {
+        let mut env: Environment<i32, FixedTime> =
+            Environment::with_clock(FixedTime::from_ticks(2), 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Stochastic(Box::new(DeterministicDist::new(0.25).unwrap())),
+            ProcessDuration::Standard,
+        );
+        env.run();
+        let times: Vec<String> = env
+            .past_events
+            .iter()
+            .map(|event| event.time.to_string())
+            .collect();
+        assert_eq!(
+            times,
+            vec!["0.000", "0.250", "0.500", "0.750", "1.000", "1.250", "1.500", "1.750", "2.000"]
+        );
+        assert_eq!(env.now(), FixedTime(2000));
+        assert_eq!(FixedTime::from_delta(1.2345).ticks(), 1);
+    }
+
+    #[test]
+    fn schedule_recurring_fires_on_period() {
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        assert!(env.schedule_recurring(0, 5, 5, Some(20)).is_some());
+        assert!(env.schedule_recurring(1, 5, 5, None).is_none());
+        // A start past `max_event` is rejected without touching the existing schedule.
+        assert!(env.schedule_recurring(0, 5, 500, None).is_none());
+        assert_eq!(env.pending_events(), 1);
+        env.run();
+        let times: Vec<u64> = env.past_events.iter().map(|event| event.time).collect();
+        // The follow-up queued at 25 is consumed without resuming the process.
+        assert_eq!(times, vec![5, 10, 15, 20]);
+        assert_eq!(
+            env.stores.keys().copied().collect::<Vec<_>>(),
+            vec![5, 10, 15, 20]
+        );
+    }
+
+    #[test]
+    fn schedule_recurring_replaces_existing_schedule() {
+        let trace = |end| {
+            let mut env = Environment::new(30, 0);
+            env.logs = true;
+            env.add_process(
+                Box::new(move || loop {
+                    yield 0;
+                }),
+                ProcessExecution::Constant(3),
+                ProcessDuration::Standard,
+            );
+            env.schedule_recurring(0, 5, 5, end);
+            env.run();
+            env.past_events
+                .iter()
+                .map(|event| event.time)
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(trace(Some(20)), vec![5, 10, 15, 20]);
+        assert_eq!(trace(None), vec![5, 10, 15, 20, 25, 30]);
+    }
+
+    #[test]
+    fn max_events_caps_runaway_run() {
+        let mut env = Environment::new(u64::MAX / 2, 0);
+        env.logs = true;
+        env.max_events = Some(100);
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(1),
+            ProcessDuration::Standard,
+        );
+        assert_eq!(env.run().stop_reason, StopReason::EventCap);
+        assert_eq!(env.past_events.len(), 100);
+        assert_eq!(env.now(), 99);
+    }
+
+    #[test]
+    fn run_report_explains_stop() {
+        let build = |max_event| {
+            let mut env = Environment::new(max_event, 0);
+            env.add_process(
+                Box::new(move || {
+                    for i in 0..3 {
+                        yield i;
+                    }
+                }),
+                ProcessExecution::Constant(10),
+                ProcessDuration::Standard,
+            );
+            env
+        };
+
+        let report = build(100).run();
+        assert_eq!(report.stop_reason, StopReason::QueueEmpty);
+        assert_eq!(report.events_executed, 4);
+        assert_eq!(report.final_time, 30);
+
+        let mut env = build(100);
+        let report = env.run_until(15);
+        assert_eq!(report.stop_reason, StopReason::ReachedStop);
+        assert_eq!(report.events_executed, 2);
+        assert_eq!(report.final_time, 10);
+
+        let mut env = build(100);
+        env.max_events = Some(1);
+        let report = env.run();
+        assert_eq!(report.stop_reason, StopReason::EventCap);
+        assert_eq!(report.events_executed, 1);
+
+        // The event closing a finite window resumes nothing and is not counted.
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Finite(0, 25),
+        );
+        let report = env.run();
+        assert_eq!(report.events_executed, 3);
+        assert_eq!(env.past_events.len(), 3);
+    }
+
+    #[test]
+    fn pending_and_executed_event_counts() {
+        let mut env = Environment::new(30, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        env.add_events(0, 5);
+        assert_eq!(env.pending_events(), 2);
+        assert_eq!(env.event_count(), 0);
+        let report = env.run();
+        assert_eq!(env.pending_events(), 0);
+        assert_eq!(env.event_count(), report.events_executed);
+    }
+
+    #[test]
+    fn debug_formatting_is_stable() {
+        assert_eq!(
+            format!("{:?}", ProcessExecution::Constant(5)),
+            "Constant(5)"
+        );
+        assert_eq!(
+            format!("{:?}", ProcessExecution::Deterministic(|time| time + 1)),
+            "Deterministic(..)"
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                ProcessExecution::Stochastic(Box::new(Gamma::new(7.0, 1.0).unwrap()))
+            ),
+            "Stochastic(..)"
+        );
+        assert_eq!(
+            format!("{:?}", ProcessDuration::Finite(1, 2)),
+            "Finite(1, 2)"
+        );
+        let event = Event {
+            time: 3,
+            process_id: 1,
+            priority: 0,
+            seq: 0,
+        };
+        assert_eq!(
+            format!("{:?}", event),
+            "Event { time: 3, process_id: 1, priority: 0, seq: 0 }"
+        );
+    }
+
+    #[test]
+    fn process_stats_count_resumes() {
+        let mut env = Environment::new(50, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Infinite(10),
+        );
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Infinite(100),
+        );
+        env.run();
+        assert_eq!(
+            env.process_stats(0),
+            Some(&ProcessStats {
+                runs: 5,
+                last_time: 50
+            })
+        );
+        assert_eq!(env.process_stats(1), None);
+    }
+
+    #[test]
+    fn process_streams_are_independent() {
+        let first_process_times = |with_second: bool| {
+            let mut env = Environment::new(500, 77);
+            env.logs = true;
+            for _ in 0..if with_second { 2 } else { 1 } {
+                env.add_process(
+                    Box::new(move || loop {
+                        yield 0;
+                    }),
+                    ProcessExecution::Stochastic(Box::new(Gamma::new(3.0, 2.0).unwrap())),
+                    ProcessDuration::Standard,
+                );
+            }
+            env.run();
+            env.past_events
+                .iter()
+                .filter(|event| event.process_id == 0)
+                .map(|event| event.time)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(first_process_times(false), first_process_times(true));
+    }
+
+    #[test]
+    fn jittered_execution_averages_to_period() {
+        let mut env = Environment::new(100_000, 3);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::jittered(10, Uniform::new(0.0, 4.0).unwrap()).unwrap(),
+            ProcessDuration::Standard,
+        );
+        env.run();
+        let deltas: Vec<u64> = env
+            .past_events
+            .windows(2)
+            .map(|pair| pair[1].time - pair[0].time)
+            .collect();
+        assert!(deltas.iter().all(|delta| (8..=12).contains(delta)));
+        let mean = deltas.iter().sum::<u64>() as f64 / deltas.len() as f64;
+        assert!((mean - 10.0).abs() < 0.1);
+        let truncated = Truncated::new(Gamma::new(2.0, 1.0).unwrap(), 0.0, 4.0).unwrap();
+        assert_eq!(
+            ProcessExecution::jittered(10, truncated).err(),
+            Some(DistributionError::NoMean)
+        );
+    }
+
+    #[test]
+    fn antithetic_pairs_reduce_variance() {
+        let builder = |seed, reflected| {
+            let mut env = Environment::new(300, seed);
+            env.add_process(
+                Box::new(move || loop {
+                    yield 0;
+                }),
+                ProcessExecution::Stochastic(Box::new(
+                    Antithetic::new(Uniform::new(1.0, 21.0).unwrap(), reflected).unwrap(),
+                )),
+                ProcessDuration::Standard,
+            );
+            env
+        };
+        let events_fired = |env: &Environment<i32>| env.stores.len() as f64;
+        let variance = |estimates: &[f64]| {
+            let mean = estimates.iter().sum::<f64>() / estimates.len() as f64;
+            estimates.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (estimates.len() - 1) as f64
+        };
+
+        let mut antithetic = Manager::new();
+        antithetic.replicate_antithetic(builder, 200, 0);
+        let paired = antithetic.run_antithetic(events_fired);
+
+        let mut independent = Manager::new();
+        independent.replicate(|seed| builder(seed, false), 400, 1_000);
+        let unpaired = independent.run_antithetic(events_fired);
+
+        assert_eq!(paired.len(), 200);
+        assert!(variance(&paired) < variance(&unpaired) / 2.0);
+    }
+
+    #[test]
+    fn inverse_cdf_closed_forms() {
+        let exponential = Exponential::new(4.0).unwrap();
+        let median = exponential.inv_cdf(0.5).unwrap();
+        assert!((median - 2f64.ln() / 4.0).abs() < 1e-12);
+        assert_eq!(exponential.inv_cdf(0.0), Some(0.0));
+
+        let uniform = Uniform::new(2.0, 6.0).unwrap();
+        assert_eq!(uniform.inv_cdf(0.25), Some(3.0));
+        assert_eq!(Gamma::new(7.0, 1.0).unwrap().inv_cdf(0.5), None);
+        assert_eq!(
+            Exponential::new(0.0).err(),
+            Some(DistributionError::NonPositiveParameter)
+        );
+        assert_eq!(
+            Exponential::new(f64::INFINITY).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+    }
+
+    #[test]
+    fn latin_hypercube_hits_every_stratum() {
+        let mut manager: Manager<i32> = Manager::new();
+        let design =
+            manager.replicate_lhs(|row| Environment::new(10, (row[0] * 1e6) as u64), 8, 3, 5);
+        assert_eq!(manager.simulations.len(), 8);
+        for dim in 0..3 {
+            let mut strata: Vec<usize> =
+                design.iter().map(|row| (row[dim] * 8.0) as usize).collect();
+            strata.sort();
+            assert_eq!(strata, (0..8).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn warmup_discards_transient() {
+        let mut manager = Manager::new();
+        manager.replicate(
+            |seed| {
+                let mut env = Environment::new(100, seed);
+                env.add_process(
+                    Box::new(move || {
+                        // Ramps up from empty to a steady level of 10.
+                        let mut level = 0.0;
+                        loop {
+                            yield level;
+                            level = f64::min(level + 2.0, 10.0);
+                        }
+                    }),
+                    ProcessExecution::Constant(5),
+                    ProcessDuration::Standard,
+                );
+                env
+            },
+            3,
+            0,
+        );
+        manager.run();
+        let overall_mean = |summary: &std::collections::BTreeMap<u64, Summary>| {
+            summary.values().map(|stats| stats.mean).sum::<f64>() / summary.len() as f64
+        };
+        let full = manager.summary();
+        let trimmed = manager.summary_with_warmup(50);
+        assert!(trimmed.keys().all(|time| *time >= 50));
+        assert!(overall_mean(&full) < 10.0);
+        assert_eq!(overall_mean(&trimmed), 10.0);
+    }
+
+    #[test]
+    fn drain_remaining_returns_unprocessed_events() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        env.seed_schedule(0, &[70, 10, 90, 30, 50]);
+        env.run_until(40);
+        let remaining: Vec<u64> = env
+            .drain_remaining()
+            .iter()
+            .map(|event| event.time)
+            .collect();
+        assert_eq!(remaining, vec![50, 70, 90]);
+        assert_eq!(env.pending_events(), 0);
+    }
+
+    #[test]
+    fn completion_hook_starts_next_process() {
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || {
+                for i in 0..2 {
+                    yield i;
+                }
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        env.add_process(
+            Box::new(move || {
+                yield 100;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        env.on_complete(0, 1, 7);
+        env.run();
+        let trace: Vec<(u64, usize)> = env
+            .past_events
+            .iter()
+            .map(|event| (event.time, event.process_id))
+            .collect();
+        // Process 0 yields at 0 and 10 and completes at 20, so process 1 runs at 27.
+        assert_eq!(trace, vec![(0, 0), (10, 0), (20, 0), (27, 1)]);
+        assert_eq!(env.stores.get(&27), Some(&100));
+    }
+
+    #[test]
+    fn monitor_samples_on_schedule() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || {
+                let mut level = 0;
+                loop {
+                    level += 1;
+                    yield level;
+                }
+            }),
+            ProcessExecution::Constant(5),
+            ProcessDuration::Standard,
+        );
+        let id = env.add_monitor(10, |env: &Environment<u64>| {
+            env.stores.values().last().copied().unwrap_or(0) as f64
+        });
+        env.run();
+        let samples = &env.monitors[id];
+        assert_eq!(samples.len(), 11);
+        // The process has yielded 1 at time 0 and 2t/10 + 1 by time t.
+        assert_eq!(samples[0], (0, 1.0));
+        assert_eq!(samples[3], (30, 7.0));
+        assert_eq!(samples[10], (100, 21.0));
+    }
+
+    #[test]
+    fn process_execution_conversions() {
+        assert!(matches!(
+            ProcessExecution::from(5),
+            ProcessExecution::Constant(5)
+        ));
+        assert!(matches!(
+            ProcessExecution::constant(3),
+            ProcessExecution::Constant(3)
+        ));
+        let distribution: Box<dyn Distribution> = Box::new(Exponential::new(2.0).unwrap());
+        assert!(matches!(
+            ProcessExecution::from(distribution),
+            ProcessExecution::Stochastic(_)
+        ));
+        let execution: ProcessExecution = 7.into();
+        let mut env = Environment::new(20, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            execution,
+            ProcessDuration::Standard,
+        );
+        env.logs = true;
+        env.run();
+        assert_eq!(env.event_count(), 3);
+        assert!(matches!(
+            ProcessExecution::stochastic(Uniform::new(1.0, 2.0).unwrap()),
+            ProcessExecution::Stochastic(_)
+        ));
+    }
+
+    #[test]
+    fn clone_without_processes_copies_state() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        env.step_n(3);
+        let mut copy = env.clone_without_processes();
+        assert!(copy.processes.is_empty());
+        assert_eq!(copy.now(), env.now());
+        assert_eq!(copy.stores, env.stores);
+        assert_eq!(
+            copy.drain_remaining()
+                .iter()
+                .map(|event| (event.time, event.process_id))
+                .collect::<Vec<_>>(),
+            vec![(30, 0)]
+        );
+        // Draining the copy leaves the original queue untouched.
+        assert_eq!(env.peek_next_time(), Some(30));
+        env.run();
+        assert_eq!(copy.stores.len(), 3);
+        assert_eq!(env.stores.len(), 11);
+    }
+
+    #[test]
+    fn clone_without_processes_replays_after_reregistration() {
+        let process = |value: u64| -> Process<u64> {
+            Box::new(move || loop {
+                yield value;
+            })
+        };
+        let execution = || ProcessExecution::Stochastic(Box::new(Exponential::new(0.2).unwrap()));
+        let mut env = Environment::new(200, 8);
+        env.logs = true;
+        for value in [1, 2] {
+            env.add_process(process(value), execution(), ProcessDuration::Standard);
+        }
+        env.step_n(3);
+
+        // A process added to a copy is new: it gets a fresh id and is scheduled from its own duration.
+        let mut other = env.clone_without_processes();
+        let id = other.add_process(
+            process(3),
+            ProcessExecution::Constant(7),
+            ProcessDuration::Infinite(50),
+        );
+        assert_eq!(id, 2);
+        let start = other.now() + 50;
+        assert!(other
+            .drain_remaining()
+            .iter()
+            .any(|event| (event.time, event.process_id) == (start, 2)));
+
+        let mut copy = env.clone_without_processes();
+        for (id, value) in [(0, 1), (1, 2)] {
+            assert!(copy.restore_process(
+                id,
+                process(value),
+                execution(),
+                ProcessDuration::Standard
+            ));
+        }
+        assert!(!copy.restore_process(0, process(1), execution(), ProcessDuration::Standard));
+        assert_eq!(copy.pending_events(), env.pending_events());
+        env.run();
+        copy.run();
+        assert_eq!(copy.stores, env.stores);
+        assert_eq!(
+            copy.past_events
+                .iter()
+                .map(|event| (event.time, event.process_id))
+                .collect::<Vec<_>>(),
+            env.past_events
+                .iter()
+                .map(|event| (event.time, event.process_id))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn triangular_and_beta_stay_in_range() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let triangular = Triangular::new(2.0, 3.0, 10.0).unwrap();
+        let samples = triangular.sample_n(&mut rng, 10_000);
+        assert!(samples.iter().all(|x| (2.0..=10.0).contains(x)));
+        let mut buckets = [0; 8];
+        for x in &samples {
+            buckets[((x - 2.0) as usize).min(7)] += 1;
+        }
+        let most_frequent = (0..8).max_by_key(|i| buckets[*i]).unwrap();
+        // The mode at 3.0 sits on the boundary of the first two unit buckets.
+        assert!(most_frequent <= 1);
+        let beta = Beta::new(2.0, 5.0, 4.0, 8.0).unwrap();
+        assert!(beta
+            .sample_n(&mut rng, 10_000)
+            .iter()
+            .all(|x| (4.0..=8.0).contains(x)));
+        assert_eq!(
+            Triangular::new(2.0, 11.0, 10.0).err(),
+            Some(DistributionError::ModeOutOfRange)
+        );
+        assert_eq!(
+            Beta::new(2.0, 5.0, 8.0, 8.0).err(),
+            Some(DistributionError::EmptyRange)
+        );
+        assert_eq!(
+            Triangular::new(f64::NAN, 1.0, 2.0).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+        assert_eq!(
+            Beta::new(2.0, 5.0, 0.0, f64::INFINITY).err(),
+            Some(DistributionError::NonFiniteParameter)
+        );
+    }
+
+    #[test]
+    fn discrete_distributions_return_whole_numbers() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        let geometric = Geometric::new(0.25).unwrap();
+        let samples = geometric.sample_n(&mut rng, 20_000);
+        assert!(samples.iter().all(|x| x.fract() == 0.0 && *x >= 1.0));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 4.0).abs() < 0.1);
+        let bernoulli = Bernoulli::new(0.5).unwrap();
+        assert!(bernoulli
+            .sample_n(&mut rng, 100)
+            .iter()
+            .all(|x| *x == 0.0 || *x == 1.0));
+        let discrete = DiscreteUniform::new(3, 6).unwrap();
+        assert!(discrete
+            .sample_n(&mut rng, 100)
+            .iter()
+            .all(|x| x.fract() == 0.0 && (3.0..=6.0).contains(x)));
+        assert_eq!(
+            Geometric::new(0.0).err(),
+            Some(DistributionError::InvalidProbability)
+        );
+        assert_eq!(
+            DiscreteUniform::new(6, 3).err(),
+            Some(DistributionError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn composite_process_runs_stages_in_order() {
+        let mut env = Environment::new(100, 0);
+        let first: Process<u64> = Box::new(move || {
+            yield 1;
+            yield 2;
+        });
+        let second: Process<u64> = Box::new(move || {
+            yield 10;
+        });
+        env.add_process(
+            Box::new(CompositeProcess::new(vec![first, second])),
+            ProcessExecution::Constant(5),
+            ProcessDuration::Standard,
+        );
+        let report = env.run();
+        assert_eq!(
+            env.stores.into_iter().collect::<Vec<_>>(),
+            vec![(0, 1), (5, 2), (10, 10)]
+        );
+        // The final resume completes the second stage and schedules nothing further.
+        assert_eq!(report.events_executed, 4);
+    }
+
+    #[test]
+    fn generate_arrivals_matches_rate() {
+        let mut env = Environment::new(10_000, 3);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(20_000),
+            ProcessDuration::Infinite(20_000),
+        );
+        let arrivals = env
+            .generate_arrivals(0, &Exponential::new(0.1).unwrap(), 10_000)
+            .unwrap();
+        // Expect rate * stop = 1000 arrivals, with a standard deviation of about 32.
+        assert!((900..=1100).contains(&arrivals));
+        assert_eq!(env.pending_events(), arrivals);
+        assert!(env.peek_next_time().unwrap() <= 10_000);
+    }
+
+    #[test]
+    fn generate_arrivals_rejects_zero_gaps() {
+        let mut env = Environment::<()>::new(u64::MAX, 0);
+        let stalled = DeterministicDist::new(0.0).unwrap();
+        assert_eq!(
+            env.generate_arrivals(0, &stalled, 100),
+            Err(DistributionError::NoProgress)
+        );
+        let arrivals = env
+            .generate_arrivals(0, &DeterministicDist::new(10.0).unwrap(), 100)
+            .unwrap();
+        assert_eq!(arrivals, 10);
+        assert_eq!(env.pending_events(), 10);
+    }
+
+    #[test]
+    fn higher_priority_event_runs_first() {
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        for value in [1, 2] {
+            env.add_process(
+                Box::new(move || {
+                    yield value;
+                }),
+                ProcessExecution::Constant(1000),
+                ProcessDuration::Infinite(200),
+            );
+        }
+        env.add_event_at(0, 10);
+        env.add_event_with_priority(1, 10, 5);
+        env.run();
+        let order: Vec<usize> = env
+            .past_events
+            .iter()
+            .map(|event| event.process_id)
+            .collect();
+        assert_eq!(order, vec![1, 0]);
+    }
+
+    #[test]
+    fn inject_schedules_between_runs() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        env.add_process(
+            Box::new(move || loop {
+                yield 2;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        env.run_until(25);
+        assert_eq!(env.now(), 20);
+        assert!(env.inject(1).is_some());
+        env.run_until(40);
+        assert_eq!(env.stores.get(&20), Some(&2));
+        assert_eq!(env.process_stats(1).unwrap().runs, 1);
+    }
+
+    #[cfg(feature = "plot")]
+    #[test]
+    fn plot_monitor_writes_image() {
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(5),
+            ProcessDuration::Standard,
+        );
+        let id = env.add_monitor(10, |env: &Environment<u64>| env.stores.len() as f64);
+        env.run();
+        let path = std::env::temp_dir().join("aika_plot_monitor.png");
+        match std::fs::read("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf") {
+            Ok(font) => {
+                register_plot_font(Box::leak(font.into_boxed_slice())).unwrap();
+                env.plot_monitor(id, path.to_str().unwrap()).unwrap();
+                assert!(std::fs::metadata(&path).unwrap().len() > 0);
+            }
+            // Without a font the labels cannot be drawn, which is reported rather than panicking.
+            Err(_) => assert!(env.plot_monitor(id, path.to_str().unwrap()).is_err()),
+        }
+    }
+
+    #[test]
+    fn events_iterator_yields_in_time_order() {
+        let mut env = Environment::new(50, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(7),
+            ProcessDuration::Standard,
+        );
+        env.add_process(
+            Box::new(move || loop {
+                yield 2;
+            }),
+            ProcessExecution::Stochastic(Box::new(Exponential::new(0.2).unwrap())),
+            ProcessDuration::Standard,
+        );
+        let executed: Vec<(u64, usize)> = env.events().collect();
+        assert!(!executed.is_empty());
+        assert!(executed.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert!(executed.iter().all(|(time, _)| *time <= 50));
+        assert_eq!(env.events().next(), None);
+        let mut env = Environment::new(50, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(7),
+            ProcessDuration::Standard,
+        );
+        let first: Vec<(u64, usize)> = env.events().take(2).collect();
+        assert_eq!(first, vec![(0, 0), (7, 0)]);
+        // Events that do not resume a process, such as an early event outside a finite window, are not yielded.
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(5),
+            ProcessDuration::Finite(50, 60),
+        );
+        env.add_events(0, 10);
+        assert_eq!(env.events().collect::<Vec<_>>(), vec![(50, 0), (55, 0)]);
+    }
+
+    #[test]
+    fn environment_with_small_rng_is_reproducible() {
+        use rand::rngs::SmallRng;
+        let trace = |seed| {
+            let mut env: Environment<u64, u64, SmallRng> = Environment::with_clock(500, seed);
+            env.logs = true;
+            env.add_process(
+                Box::new(move || loop {
+                    yield 1;
+                }),
+                ProcessExecution::Stochastic(Box::new(Exponential::new(0.1).unwrap())),
+                ProcessDuration::Standard,
+            );
+            env.run();
+            env.past_events
+                .iter()
+                .map(|event| event.time)
+                .collect::<Vec<u64>>()
+        };
+        assert!(trace(9).len() > 10);
+        assert_eq!(trace(9), trace(9));
+        assert_ne!(trace(9), trace(10));
+    }
+
+    #[test]
+    fn time_weighted_average_of_step_series() {
+        assert_eq!(time_weighted_average(&[(0, 0.0), (5, 10.0)], 10), 5.0);
+        // A short spike barely moves the average, unlike a plain mean of the samples.
+        assert_eq!(
+            time_weighted_average(&[(0, 2.0), (8, 12.0), (9, 2.0), (20, 50.0)], 10),
+            3.0
+        );
+        assert!(time_weighted_average(&[], 10).is_nan());
+    }
+
+    #[test]
+    fn percentile_and_histogram_of_uniform_samples() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(13);
+        let mut samples = Uniform::new(0.0, 10.0).unwrap().sample_n(&mut rng, 10_000);
+        assert!((percentile(&mut samples, 50.0) - 5.0).abs() < 0.2);
+        assert!((percentile(&mut samples, 95.0) - 9.5).abs() < 0.2);
+        assert_eq!(percentile(&mut [1.0, 2.0, 3.0, 4.0], 50.0), 2.5);
+        assert!(percentile(&mut [], 50.0).is_nan());
+        let histogram = Histogram::new(1.0).samples(&samples);
+        assert_eq!(histogram.counts.len(), 10);
+        assert_eq!(histogram.total(), samples.len());
+        assert_eq!(
+            histogram.bins().map(|(_, count)| count).sum::<usize>(),
+            samples.len()
+        );
+        let shifted = Histogram::new(2.0)
+            .start(4.0)
+            .samples(&[1.0, 4.0, 5.5, 6.0]);
+        assert_eq!(shifted.underflow, 1);
+        assert_eq!(shifted.bins().collect::<Vec<_>>(), vec![(4.0, 2), (6.0, 1)]);
+    }
+
+    #[test]
+    fn histogram_counts_non_finite_and_distant_values_as_overflow() {
+        let mut histogram = Histogram::new(1.0).max_bins(4);
+        for value in [
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+            1e300,
+            4.0,
+            3.5,
+            -1.0,
+        ] {
+            histogram.add(value);
+        }
+        assert_eq!(histogram.overflow, 5);
+        assert_eq!(histogram.underflow, 1);
+        assert_eq!(histogram.counts, vec![0, 0, 0, 1]);
+        assert_eq!(histogram.total(), 7);
+    }
+
+    #[test]
+    fn add_process_returns_id() {
+        let mut env = Environment::new(100, 0);
+        let mut ids = Vec::new();
+        for value in [1, 2] {
+            ids.push(env.add_process(
+                Box::new(move || loop {
+                    yield value;
+                }),
+                ProcessExecution::Constant(1000),
+                ProcessDuration::Infinite(200),
+            ));
+        }
+        let named = env.add_process_named(
+            "third",
+            Box::new(move || loop {
+                yield 3;
+            }),
+            ProcessExecution::Constant(1000),
+            ProcessDuration::Infinite(200),
+        );
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(env.process_id_by_name("third"), Some(named));
+        env.add_event_at(ids[1], 10);
+        env.add_event_at(named, 20);
+        env.run();
+        assert_eq!(
+            env.stores.into_iter().collect::<Vec<_>>(),
+            vec![(10, 2), (20, 3)]
+        );
+    }
+
+    #[test]
+    fn completed_processes_are_removed() {
+        let mut env = Environment::new(100, 0);
+        let finished = env.add_process_named(
+            "finished",
+            Box::new(move || {
+                yield 1;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        let windowed = env.add_process(
+            Box::new(move || loop {
+                yield 2;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Finite(0, 25),
+        );
+        let removed = env.add_process(
+            Box::new(move || loop {
+                yield 3;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        // The late event is dropped along with the completed process instead of resuming a finished generator.
+        env.add_event_at(finished, 50);
+        env.run_until(5);
+        assert!(env.remove_process(removed));
+        assert!(!env.remove_process(removed));
+        env.run();
+        assert!(env.processes.is_empty());
+        assert_eq!(env.process_id_by_name("finished"), None);
+        assert_eq!(env.process_stats(finished).unwrap().runs, 2);
+        assert_eq!(env.process_stats(windowed).unwrap().runs, 3);
+        assert_eq!(env.process_stats(removed).unwrap().runs, 1);
+        let next = env.add_process(
+            Box::new(move || {
+                yield 4;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        assert_eq!(next, 3);
+    }
+
+    #[test]
+    fn custom_execution_depends_on_state() {
+        use std::sync::Arc;
+        let mut env = Environment::new(100, 0);
+        env.add_process(
+            Box::new(move || {
+                let mut produced = 0;
+                loop {
+                    produced += 1;
+                    yield produced;
+                }
+            }),
+            ProcessExecution::Custom(Arc::new(|context: &mut StepContext| {
+                // Back off by one more unit per run, then settle once time 10 is reached.
+                if context.now < 10 {
+                    context.runs + 1
+                } else {
+                    5
+                }
+            })),
+            ProcessDuration::Standard,
+        );
+        env.step_n(6);
+        assert_eq!(
+            env.stores.keys().copied().collect::<Vec<_>>(),
+            vec![0, 1, 3, 6, 10, 15]
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                ProcessExecution::Custom(Arc::new(|context: &mut StepContext| context.runs))
+            ),
+            "Custom(..)"
+        );
+    }
+
+    #[test]
+    fn on_step_hook_fires_per_event() {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut env = Environment::new(30, 0);
+        for value in [1, 2] {
+            env.add_process(
+                Box::new(move || {
+                    for _ in 0..2 {
+                        yield value;
+                    }
+                }),
+                ProcessExecution::Constant(10),
+                ProcessDuration::Infinite(value),
+            );
+        }
+        let hook_seen = seen.clone();
+        env.set_on_step(move |event, value: Option<&u64>| {
+            hook_seen
+                .lock()
+                .unwrap()
+                .push((event.time, event.process_id, value.copied()));
+        });
+        let report = env.run();
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), report.events_executed);
+        assert_eq!(
+            *seen,
+            vec![
+                (1, 0, Some(1)),
+                (2, 1, Some(2)),
+                (11, 0, Some(1)),
+                (12, 1, Some(2)),
+                (21, 0, None),
+                (22, 1, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn huge_delta_does_not_overflow() {
+        let mut env = Environment::new(u64::MAX, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(u64::MAX),
+            ProcessDuration::Infinite(5),
+        );
+        let report = env.run();
+        assert_eq!(report.events_executed, 1);
+        assert_eq!(report.stop_reason, StopReason::QueueEmpty);
+        assert_eq!(env.add_events(0, u64::MAX), None);
+        assert_eq!(env.peek_next_time(), None);
+        assert!(env.add_events(0, u64::MAX - 5).is_some());
+        assert_eq!(env.peek_next_time(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn clock_maps_ticks_to_real_time() {
+        use std::time::Duration;
+        let clock = Clock::new(Duration::from_millis(100));
+        assert_eq!(clock.to_real(30), Duration::from_secs(3));
+        assert_eq!(clock.tick_duration(), Duration::from_millis(100));
+        assert_eq!(clock.from_real(Duration::from_secs(3)), 30);
+        assert_eq!(clock.from_real(Duration::from_millis(250)), 2);
+        let mut env: Environment<u64> = Environment::new(100, 0);
+        env.clock = Some(clock);
+        assert_eq!(env.clock.unwrap().to_real(env.now()), Duration::ZERO);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn export_events_csv_adds_real_time_with_a_clock() {
+        use std::time::Duration;
+        let mut env = Environment::new(30, 0);
+        env.logs = true;
+        env.clock = Some(Clock::new(Duration::from_millis(100)));
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(15),
+            ProcessDuration::Standard,
+        );
+        env.run();
+        let mut csv = Vec::new();
+        env.export_events_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time,process_id,real_seconds\n0,0,0\n15,0,1.5\n30,0,3\n"
+        );
+    }
+
+    #[test]
+    fn markov_modulated_rate_matches_stationary_average() {
+        let arrivals = MarkovModulated::new(
+            vec![1.0, 0.1],
+            vec![vec![0.0, 1.0], vec![1.0, 0.0]],
+            vec![
+                Box::new(Exponential::new(0.1).unwrap()),
+                Box::new(Exponential::new(1.0 / 30.0).unwrap()),
+            ],
+        )
+        .unwrap();
+        let mut env = Environment::new(100_000, 21);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(200_000),
+            ProcessDuration::Infinite(200_000),
+        );
+        let count = env
+            .generate_modulated_arrivals(0, &arrivals, 100_000)
+            .unwrap();
+        // The chain spends 10 / 40 of the time at rate 1.0 and 30 / 40 at rate 0.1.
+        let expected = 100_000.0 * (0.25 * 1.0 + 0.75 * 0.1);
+        assert!((count as f64 - expected).abs() < 0.05 * expected);
+        assert_eq!(env.pending_events(), count);
+        assert_eq!(
+            MarkovModulated::new(vec![1.0], vec![vec![1.0, 0.0]], vec![]).err(),
+            Some(DistributionError::DimensionMismatch)
+        );
+    }
+
+    #[test]
+    fn markov_modulated_rejects_stalling_chains() {
+        let modulated = |rate: f64, holding: Box<dyn Distribution>| {
+            MarkovModulated::new(vec![rate], vec![vec![1.0]], vec![holding]).err()
+        };
+        assert_eq!(
+            modulated(1.0, Box::new(DeterministicDist::new(0.0).unwrap())),
+            Some(DistributionError::NonPositiveParameter)
+        );
+        assert_eq!(
+            modulated(f64::INFINITY, Box::new(Exponential::new(1.0).unwrap())),
+            Some(DistributionError::NonFiniteParameter)
+        );
+        assert_eq!(
+            MarkovModulated::new(
+                vec![1.0],
+                vec![vec![f64::INFINITY]],
+                vec![Box::new(Exponential::new(1.0).unwrap())]
+            )
+            .err(),
+            Some(DistributionError::InvalidWeights)
+        );
+        // A Bernoulli holding time has a positive mean but is zero with probability one half.
+        let arrivals = MarkovModulated::new(
+            vec![1.0],
+            vec![vec![1.0]],
+            vec![Box::new(Bernoulli::new(0.5).unwrap())],
+        )
+        .unwrap();
+        let mut env = Environment::<()>::new(u64::MAX, 0);
+        let mut outcomes = (0..20).map(|_| env.generate_modulated_arrivals(0, &arrivals, 1_000));
+        assert!(outcomes.any(|outcome| outcome == Err(DistributionError::NoProgress)));
+    }
+
+    #[test]
+    fn time_series_of_projects_past_events() {
+        let mut env = Environment::new(100, 0);
+        env.logs = true;
+        for value in [1, 2, 3] {
+            env.add_process(
+                Box::new(move || {
+                    yield value;
+                }),
+                ProcessExecution::Constant(1000),
+                ProcessDuration::Infinite(200),
+            );
+        }
+        env.seed_schedule(2, &[5]);
+        env.seed_schedule(0, &[10]);
+        env.seed_schedule(1, &[20]);
+        env.run();
+        let series = env.time_series_of(|event| event.process_id as f64);
+        assert_eq!(
+            series.into_iter().collect::<Vec<_>>(),
+            vec![(5, 2.0), (10, 0.0), (20, 1.0)]
+        );
+    }
+
+    #[test]
+    fn invalid_parameters_return_errors() {
+        assert_eq!(
+            Gamma::new(-1.0, 1.0).err(),
+            Some(DistributionError::NonPositiveParameter)
+        );
+        assert_eq!(
+            Gamma::new(1.0, 0.0).err(),
+            Some(DistributionError::NonPositiveParameter)
+        );
+        assert_eq!(
+            Poisson::new(f64::NAN).err(),
+            Some(DistributionError::NonPositiveParameter)
+        );
+        assert!(Poisson::new(3.0).is_ok());
+    }
+
+    #[test]
+    fn reported_moments_match_samples() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(17);
+        let gamma = Gamma::new(3.0, 2.0).unwrap();
+        let samples = gamma.sample_n(&mut rng, 20_000);
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (samples.len() - 1) as f64;
+        assert_eq!(gamma.mean(), Some(6.0));
+        assert_eq!(gamma.variance(), Some(12.0));
+        assert!((mean - 6.0).abs() < 0.1);
+        assert!((variance - 12.0).abs() < 0.5);
+        assert_eq!(Uniform::new(2.0, 8.0).unwrap().variance(), Some(3.0));
+        assert_eq!(DiscreteUniform::new(1, 6).unwrap().mean(), Some(3.5));
+        assert_eq!(
+            DiscreteUniform::new(1, 6).unwrap().variance(),
+            Some(35.0 / 12.0)
+        );
+        let full_range = DiscreteUniform::new(0, u64::MAX).unwrap();
+        assert_eq!(full_range.mean(), Some(u64::MAX as f64 / 2.0));
+        assert_eq!(full_range.variance(), Some((2f64.powi(128) - 1.0) / 12.0));
+        assert_eq!(
+            Empirical::new(vec![1.0, 3.0]).unwrap().variance(),
+            Some(1.0)
+        );
+        assert_eq!(HalfNormal::new(1.0).unwrap().mean(), None);
+    }
+
+    #[test]
+    fn replay_reproduces_recorded_times() {
+        let build = |seed| {
+            let mut env = Environment::new(200, seed);
+            env.logs = true;
+            env.add_process(
+                Box::new(move || loop {
+                    yield 1;
+                }),
+                ProcessExecution::Stochastic(Box::new(Exponential::new(0.1).unwrap())),
+                ProcessDuration::Standard,
+            );
+            env
+        };
+        let mut recorded = build(1);
+        recorded.run();
+        let trace = recorded.past_events.clone();
+        let mut replayed = build(2);
+        replayed.events.clear();
+        let report = replayed.replay(&trace);
+        assert_eq!(report.events_executed, trace.len());
+        assert_eq!(report.stop_reason, StopReason::QueueEmpty);
+        let times = |env: &Environment<u64>| {
+            env.past_events
+                .iter()
+                .map(|event| (event.time, event.process_id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(times(&replayed), times(&recorded));
+    }
+
+    #[test]
+    fn default_environment_runs() {
+        let mut env: Environment<u64> = Environment::default();
+        assert_eq!(env.max_event, u64::MAX);
+        assert_eq!(env.seed, 0);
+        let report = env.run();
+        assert_eq!(report.events_executed, 0);
+        assert_eq!(report.stop_reason, StopReason::QueueEmpty);
+        env.add_process(
+            Box::new(move || {
+                yield 1;
+            }),
+            ProcessExecution::Constant(10),
+            ProcessDuration::Standard,
+        );
+        assert_eq!(env.run().events_executed, 2);
+    }
+
+    #[test]
+    fn monitor_stops_when_queue_drains() {
+        let mut env = Environment::new(1_000_000, 0);
+        env.add_process(
+            Box::new(move || {
+                yield 1;
+                yield 2;
+            }),
+            ProcessExecution::Constant(3),
+            ProcessDuration::Standard,
+        );
+        let id = env.add_monitor(1, |env: &Environment<u64>| env.stores.len() as f64);
+        let report = env.run();
+        assert_eq!(report.stop_reason, StopReason::QueueEmpty);
+        // Samples stop at the completing resume at time 6 instead of running on to `max_event`.
+        assert_eq!(env.monitors[id].len(), 7);
+        assert_eq!(env.monitors[id].last(), Some(&(6, 2.0)));
+        let mut env: Environment<u64> = Environment::default();
+        let id = env.add_monitor(1, |env: &Environment<u64>| env.stores.len() as f64);
+        env.run();
+        assert_eq!(env.monitors[id], vec![(0, 0.0)]);
+
+        // Stopping before a distant event does not fill the idle time up to `until` with samples.
+        let mut env = Environment::new(u64::MAX, 0);
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Constant(1_000_000_000),
+            ProcessDuration::Standard,
+        );
+        let id = env.add_monitor(1, |env: &Environment<u64>| env.stores.len() as f64);
+        let report = env.run_until(20_000_000);
+        assert_eq!(report.stop_reason, StopReason::ReachedStop);
+        assert_eq!(env.monitors[id], vec![(0, 1.0)]);
+    }
+
+    #[test]
+    fn antithetic_requires_inverse_cdf() {
+        assert_eq!(
+            Antithetic::new(Gamma::new(3.0, 2.0).unwrap(), true).err(),
+            Some(DistributionError::NoInverseCdf)
+        );
+        assert!(Antithetic::new(Exponential::new(0.5).unwrap(), true).is_ok());
+    }
+
+    #[test]
+    fn scheduler_runs_with_a_fixed_rng() {
+        // A generator that only counts up, so the run does not depend on `StdRng` or the `std` feature.
+        struct CountingRng(u64);
+
+        impl rand::RngCore for CountingRng {
+            fn next_u32(&mut self) -> u32 {
+                self.next_u64() as u32
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                self.0 = self.0.wrapping_add(1);
+                self.0
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                for chunk in dest.chunks_mut(8) {
+                    chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+                }
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        impl rand::SeedableRng for CountingRng {
+            type Seed = [u8; 8];
+
+            fn from_seed(seed: Self::Seed) -> Self {
+                CountingRng(u64::from_le_bytes(seed))
+            }
+        }
+
+        let mut env: Environment<u64, u64, CountingRng> = Environment::with_clock(15, 0);
+        env.logs = true;
+        env.add_process(
+            Box::new(move || loop {
+                yield 0;
+            }),
+            ProcessExecution::Constant(4),
+            ProcessDuration::Standard,
+        );
+        env.add_process(
+            Box::new(move || loop {
+                yield 1;
+            }),
+            ProcessExecution::Stochastic(Box::new(DeterministicDist::new(5.0).unwrap())),
+            ProcessDuration::Standard,
+        );
+        env.run();
+        assert_eq!(
+            env.past_events
+                .iter()
+                .map(|event| (event.time, event.process_id))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 0),
+                (0, 1),
+                (4, 0),
+                (5, 1),
+                (8, 0),
+                (10, 1),
+                (12, 0),
+                (15, 1)
+            ]
+        );
+    }
 }
@@ -1,4 +1,10 @@
-use std::collections::BTreeMap;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 
 use crate::environment::Environment;
 
@@ -10,6 +16,12 @@ pub struct Manager<T: Clone> {
     pub stores: Vec<BTreeMap<u64, T>>,
 }
 
+impl<T: Clone> Default for Manager<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Clone> Manager<T> {
     /// Create a new `Manager` struct.
     pub fn new() -> Self {
@@ -24,11 +36,158 @@ impl<T: Clone> Manager<T> {
         self.simulations.push(simulation);
     }
 
+    /// Add `n` simulations built by `builder`, seeded with `base_seed + i` for replication `i`.
+    pub fn replicate<F: Fn(u64) -> Environment<T>>(
+        &mut self,
+        builder: F,
+        n: usize,
+        base_seed: u64,
+    ) {
+        for i in 0..n as u64 {
+            self.add_simulation(builder(base_seed + i));
+        }
+    }
+
+    /// Add `n` simulations built from the rows of a Latin-hypercube design over `[0, 1]^dims`. Each dimension is split
+    /// into `n` equal strata and every stratum is sampled by exactly one row. `seed` drives the design, and the
+    /// builder maps each row to simulation parameters. Returns the design rows in the order the simulations were added.
+    pub fn replicate_lhs<F: Fn(&[f64]) -> Environment<T>>(
+        &mut self,
+        builder: F,
+        n: usize,
+        dims: usize,
+        seed: u64,
+    ) -> Vec<Vec<f64>> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let mut design = vec![Vec::with_capacity(dims); n];
+        let mut strata: Vec<usize> = (0..n).collect();
+        for _ in 0..dims {
+            strata.shuffle(&mut rng);
+            for (row, stratum) in design.iter_mut().zip(&strata) {
+                row.push((*stratum as f64 + rng.gen::<f64>()) / n as f64);
+            }
+        }
+        for row in &design {
+            self.add_simulation(builder(row));
+        }
+        design
+    }
+
+    /// Add `pairs` antithetic pairs of simulations built by `builder(seed, reflected)`. Both simulations of pair `i`
+    /// share the seed `base_seed + i`; the second is built with `reflected` set, e.g. to construct `Antithetic`
+    /// distributions.
+    pub fn replicate_antithetic<F: Fn(u64, bool) -> Environment<T>>(
+        &mut self,
+        builder: F,
+        pairs: usize,
+        base_seed: u64,
+    ) {
+        for i in 0..pairs as u64 {
+            self.add_simulation(builder(base_seed + i, false));
+            self.add_simulation(builder(base_seed + i, true));
+        }
+    }
+
+    /// Run all simulations as antithetic pairs `(0, 1)`, `(2, 3)`, ... and return the mean of `estimator` over each
+    /// pair. A trailing unpaired simulation is run but not included.
+    pub fn run_antithetic<F: Fn(&Environment<T>) -> f64>(&mut self, estimator: F) -> Vec<f64> {
+        self.run();
+        self.simulations
+            .chunks_exact(2)
+            .map(|pair| (estimator(&pair[0]) + estimator(&pair[1])) / 2.0)
+            .collect()
+    }
+
     /// Run all simulations in the `Manager` struct.
     pub fn run(&mut self) {
-        for simulation in &mut self.simulations {
+        self.run_with_progress(|_, _| {});
+    }
+
+    /// Run all simulations in the `Manager` struct, calling `progress(completed, total)` after each one finishes.
+    pub fn run_with_progress<F: FnMut(usize, usize)>(&mut self, mut progress: F) {
+        let total = self.simulations.len();
+        for (i, simulation) in self.simulations.iter_mut().enumerate() {
             simulation.run();
             self.stores.push(simulation.stores.clone());
+            progress(i + 1, total);
         }
     }
 }
+
+/// Summary statistics of a value across replications at a single timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    /// The sample mean.
+    pub mean: f64,
+    /// The unbiased sample variance. Zero when fewer than two replications contributed.
+    pub variance: f64,
+    /// The normal-approximation 95% confidence interval for the mean.
+    pub ci95: (f64, f64),
+    /// The number of replications that recorded a value at this timestamp.
+    pub n: usize,
+}
+
+impl<T: Clone + Into<f64>> Manager<T> {
+    /// Summarize the stored results per timestamp across replications. Replications without a value at a given
+    /// timestamp are skipped for that timestamp, so `n` may differ between timestamps.
+    pub fn summary(&self) -> BTreeMap<u64, Summary> {
+        self.summary_with_warmup(0)
+    }
+
+    /// Summarize the stored results like [`Manager::summary`], discarding the initial transient: timestamps before
+    /// `warmup` are ignored.
+    pub fn summary_with_warmup(&self, warmup: u64) -> BTreeMap<u64, Summary> {
+        let mut samples: BTreeMap<u64, Vec<f64>> = BTreeMap::new();
+        for store in &self.stores {
+            for (time, value) in store.range(warmup..) {
+                samples.entry(*time).or_default().push(value.clone().into());
+            }
+        }
+        samples
+            .into_iter()
+            .map(|(time, values)| (time, Summary::from_samples(&values)))
+            .collect()
+    }
+}
+
+impl Summary {
+    /// Compute summary statistics for a non-empty set of samples.
+    fn from_samples(values: &[f64]) -> Self {
+        let n = values.len();
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1) as f64
+        } else {
+            0.0
+        };
+        let half_width = 1.96 * (variance / n as f64).sqrt();
+        Summary {
+            mean,
+            variance,
+            ci95: (mean - half_width, mean + half_width),
+            n,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Clone + Send> Manager<T> {
+    /// Run all simulations in the `Manager` struct in parallel. Each `Environment` owns its seeded `rng`, so results
+    /// match a sequential `run`.
+    pub fn run_parallel(&mut self) {
+        use rayon::prelude::*;
+
+        self.simulations.par_iter_mut().for_each(|simulation| {
+            simulation.run();
+        });
+        self.stores.extend(
+            self.simulations
+                .iter()
+                .map(|simulation| simulation.stores.clone()),
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Stats module. Contains helpers for summarizing series and samples recorded during a simulation, such as the
+//! `monitors` of an `Environment` or waiting times collected by a process.
+
+use alloc::vec::Vec;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+
+/// The time-weighted average of a step function up to `end`. Each `(time, value)` sample in `series`, sorted by time,
+/// holds until the next sample or `end`, and samples at or after `end` are ignored. The average is taken over
+/// `[first sample time, end)`. Returns `NaN` when the series is empty or spans no time.
+pub fn time_weighted_average(series: &[(u64, f64)], end: u64) -> f64 {
+    let start = match series.first() {
+        Some((start, _)) if *start < end => *start,
+        _ => return f64::NAN,
+    };
+    let area: f64 = series
+        .iter()
+        .zip(series.iter().skip(1).map(|(time, _)| *time).chain([end]))
+        .map(|((time, value), next)| (next.min(end).saturating_sub(*time)) as f64 * value)
+        .sum();
+    area / (end - start) as f64
+}
+
+/// The `p`-th percentile of `samples`, for `p` in `[0, 100]`, interpolating linearly between the two closest ranks.
+/// Sorts `samples` in place. Returns `NaN` when `samples` is empty.
+pub fn percentile(samples: &mut [f64], p: f64) -> f64 {
+    if samples.is_empty() {
+        return f64::NAN;
+    }
+    samples.sort_by(f64::total_cmp);
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (samples.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    samples[lower] + (rank - lower as f64) * (samples[upper] - samples[lower])
+}
+
+/// A histogram with fixed-width bins starting at `start`. Bins grow as values are added, up to `max_bins`. Values
+/// below `start` are counted in `underflow`; values past the last bin and non-finite values are counted in `overflow`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The lower edge of the first bin.
+    pub start: f64,
+    /// The width of every bin.
+    pub bin_width: f64,
+    /// The number of values in each bin, starting with the bin covering `[start, start + bin_width)`.
+    pub counts: Vec<usize>,
+    /// The number of values below `start`.
+    pub underflow: usize,
+    /// The number of values past the last of `max_bins` bins, or not finite.
+    pub overflow: usize,
+    /// The largest number of bins the histogram grows to.
+    pub max_bins: usize,
+}
+
+impl Histogram {
+    /// The default for `max_bins`.
+    pub const DEFAULT_MAX_BINS: usize = 1024;
+
+    /// Create an empty histogram with up to `DEFAULT_MAX_BINS` bins of `bin_width` starting at zero.
+    ///
+    /// Panics if `bin_width` is not positive.
+    pub fn new(bin_width: f64) -> Self {
+        assert!(bin_width > 0.0, "bin width must be positive");
+        Histogram {
+            start: 0.0,
+            bin_width,
+            counts: Vec::new(),
+            underflow: 0,
+            overflow: 0,
+            max_bins: Self::DEFAULT_MAX_BINS,
+        }
+    }
+
+    /// Set the lower edge of the first bin.
+    pub fn start(mut self, start: f64) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Set the largest number of bins the histogram grows to.
+    pub fn max_bins(mut self, max_bins: usize) -> Self {
+        self.max_bins = max_bins;
+        self
+    }
+
+    /// Add every value in `samples`.
+    pub fn samples(mut self, samples: &[f64]) -> Self {
+        for value in samples {
+            self.add(*value);
+        }
+        self
+    }
+
+    /// Add a single value.
+    pub fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            self.overflow += 1;
+            return;
+        }
+        if value < self.start {
+            self.underflow += 1;
+            return;
+        }
+        let bin = ((value - self.start) / self.bin_width) as usize;
+        if bin >= self.max_bins {
+            self.overflow += 1;
+            return;
+        }
+        if bin >= self.counts.len() {
+            self.counts.resize(bin + 1, 0);
+        }
+        self.counts[bin] += 1;
+    }
+
+    /// The lower edge and count of each bin, in order.
+    pub fn bins(&self) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (self.start + i as f64 * self.bin_width, *count))
+    }
+
+    /// The number of values added, including `underflow` and `overflow`.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum::<usize>() + self.underflow + self.overflow
+    }
+}
@@ -0,0 +1,26 @@
+//! Threading module. Contains the thread-safety bounds placed on processes, distributions and callbacks. They only
+//! require `Send` (and `Sync`) when the `rayon` feature is enabled, so single-threaded users can capture `Rc` state.
+
+/// Requires `Send` when the `rayon` feature is enabled. Implemented automatically for every qualifying type.
+#[cfg(feature = "rayon")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "rayon")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+/// Requires `Send` when the `rayon` feature is enabled. Implemented automatically for every qualifying type.
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "rayon"))]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// Requires `Send + Sync` when the `rayon` feature is enabled. Implemented automatically for every qualifying type.
+#[cfg(feature = "rayon")]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(feature = "rayon")]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// Requires `Send + Sync` when the `rayon` feature is enabled. Implemented automatically for every qualifying type.
+#[cfg(not(feature = "rayon"))]
+pub trait MaybeSendSync {}
+#[cfg(not(feature = "rayon"))]
+impl<T: ?Sized> MaybeSendSync for T {}
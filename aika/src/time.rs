@@ -0,0 +1,116 @@
+//! Time module. Contains the `Time` trait describing the clock an `Environment` runs on, implemented for integer
+//! ticks (`u64`) and for fixed-point time (`FixedTime`) when sub-tick resolution is needed. The `Clock` struct maps time
+//! units to real durations for output.
+
+use core::fmt;
+use core::ops::Add;
+use core::time::Duration;
+#[cfg(not(any(feature = "std", test)))]
+use num_traits::Float;
+
+/// The `Time` trait allows an `Environment` to run on clocks other than integer ticks.
+pub trait Time: Copy + Ord + Default + fmt::Display + Add<Output = Self> {
+    /// Convert a whole number of time units into a clock value.
+    fn from_ticks(ticks: u64) -> Self;
+    /// Convert a non-negative, possibly fractional, number of time units such as a distribution sample into a clock value.
+    fn from_delta(delta: f64) -> Self;
+    /// The clock value truncated to whole time units.
+    fn ticks(self) -> u64;
+    /// Add `other`, returning `None` if the result cannot be represented.
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+impl Time for u64 {
+    fn from_ticks(ticks: u64) -> Self {
+        ticks
+    }
+
+    /// Fractional deltas are rounded to the nearest tick.
+    fn from_delta(delta: f64) -> Self {
+        delta.round() as u64
+    }
+
+    fn ticks(self) -> u64 {
+        self
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u64::checked_add(self, other)
+    }
+}
+
+/// Fixed-point time with a resolution of one thousandth of a time unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct FixedTime(pub u64);
+
+impl FixedTime {
+    /// The number of fixed-point steps per whole time unit.
+    pub const SCALE: u64 = 1000;
+}
+
+impl Add for FixedTime {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        FixedTime(self.0 + other.0)
+    }
+}
+
+impl fmt::Display for FixedTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:03}", self.0 / Self::SCALE, self.0 % Self::SCALE)
+    }
+}
+
+impl Time for FixedTime {
+    fn from_ticks(ticks: u64) -> Self {
+        FixedTime(ticks.saturating_mul(Self::SCALE))
+    }
+
+    /// Fractional deltas are rounded to the nearest thousandth.
+    fn from_delta(delta: f64) -> Self {
+        FixedTime((delta * Self::SCALE as f64).round() as u64)
+    }
+
+    fn ticks(self) -> u64 {
+        self.0 / Self::SCALE
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(FixedTime)
+    }
+}
+
+/// Maps whole time units to real durations for readable output. The scheduler itself stays in time units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    /// The real duration of one time unit. Private so that it cannot be set to zero.
+    tick_duration: Duration,
+}
+
+impl Clock {
+    /// Create a clock where one time unit lasts `tick_duration`.
+    ///
+    /// Panics if `tick_duration` is zero.
+    pub fn new(tick_duration: Duration) -> Self {
+        assert!(!tick_duration.is_zero(), "tick duration must be non-zero");
+        Clock { tick_duration }
+    }
+
+    /// The real duration of one time unit.
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// The real duration of `tick` time units, saturating at the largest representable duration.
+    pub fn to_real(&self, tick: u64) -> Duration {
+        let nanos = self.tick_duration.as_nanos().saturating_mul(tick as u128);
+        let secs = u64::try_from(nanos / 1_000_000_000).unwrap_or(u64::MAX);
+        Duration::new(secs, (nanos % 1_000_000_000) as u32)
+    }
+
+    /// The number of whole time units in `duration`, rounded down.
+    pub fn from_real(&self, duration: Duration) -> u64 {
+        u64::try_from(duration.as_nanos() / self.tick_duration.as_nanos()).unwrap_or(u64::MAX)
+    }
+}